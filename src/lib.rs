@@ -0,0 +1,66 @@
+//! The version manager behind the `gvm` command: Go version names, where
+//! gvm keeps wrappers and SDKs, and switching between installed SDKs.
+//! Installing, uninstalling and the other commands are still part of the
+//! `gvm` binary rather than this library.
+//!
+//! The functions here don't print or exit; failures come back as `Result`s
+//! for the caller to report. Call `paths::init` first.
+
+pub mod paths;
+pub mod sdk;
+pub mod version;
+
+/// GOOS of the machine gvm is running on
+pub fn host_go_os() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    }
+}
+
+/// GOARCH of the machine gvm is running on as Go itself names it, for
+/// setting `GOARCH` in a child's environment
+pub fn host_goarch_env() -> &'static str {
+    goarch_env(host_archive_arch())
+}
+
+/// Architecture of the machine gvm is running on as the release archives
+/// name it
+pub fn host_archive_arch() -> &'static str {
+    archive_arch(std::env::consts::ARCH)
+}
+
+fn archive_arch(rust_arch: &str) -> &str {
+    match rust_arch {
+        "x86_64" => "amd64",
+        "x86" => "386",
+        "aarch64" => "arm64",
+        "arm" => "armv6l",
+        "powerpc64" => "ppc64le",
+        "loongarch64" => "loong64",
+        arch => arch,
+    }
+}
+
+/// Only 32-bit ARM differs: its archives are named `armv6l`, but Go calls
+/// it `arm`
+fn goarch_env(archive_arch: &str) -> &str {
+    match archive_arch {
+        "armv6l" => "arm",
+        arch => arch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arm_archives_and_goarch_differ() {
+        assert_eq!(archive_arch("arm"), "armv6l");
+        assert_eq!(goarch_env(archive_arch("arm")), "arm");
+        assert_eq!(archive_arch("aarch64"), "arm64");
+        assert_eq!(goarch_env(archive_arch("aarch64")), "arm64");
+        assert_eq!(goarch_env(archive_arch("x86_64")), "amd64");
+    }
+}
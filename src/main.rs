@@ -1,10 +1,48 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use colored::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::env::consts::EXE_SUFFIX;
 use std::fs;
-use std::os::unix::fs as unix_fs;
-use std::path::PathBuf;
-use std::process::Command;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitCode, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+mod net;
+
+/// `eprintln!` for errors, warnings and hints. With `--json` they're
+/// collected instead and printed as one JSON object when gvm exits.
+macro_rules! report {
+    ($($arg:tt)*) => {
+        report_line(format!($($arg)*))
+    };
+}
+
+/// `println!` for progress messages, which `--quiet` hides
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if !QUIET.load(Ordering::Relaxed) {
+            println!($($arg)*);
+        }
+    };
+}
+
+use govm::paths::{
+    self, get_config_file, get_go_bin_dir, get_go_link, get_go_wrapper, get_history_file,
+    get_link_dir, get_release_cache_file, get_sdk_dir, get_sdk_root, get_update_check_file,
+    set_config_file, set_link_name,
+};
+use govm::sdk::{self, is_partial_sdk, switch_go_link};
+use govm::version::{
+    best_partial_match, extract_version_number, normalize_version, version_order, wrapper_version,
+    PreRelease, Version,
+};
+use govm::{host_archive_arch, host_go_os, host_goarch_env};
 
 /// GVM - Go Version Manager
 /// A simple tool to manage multiple Go versions, similar to nvm
@@ -12,376 +50,5498 @@ use std::process::Command;
 #[command(name = "gvm")]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    /// Output machine-readable JSON instead of formatted text
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Disable colored output (also disabled by NO_COLOR or when stdout
+    /// isn't a terminal)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Log the external commands gvm runs and the paths it resolves
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Only print errors; progress from installs and switches is hidden
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Mirror to use instead of https://go.dev/dl/ for the release list and
+    /// SDK downloads (overrides GVM_DOWNLOAD_BASE)
+    #[arg(long, global = true, value_name = "URL")]
+    base_url: Option<String>,
+
+    /// Proxy for all requests to go.dev, including SDK downloads
+    /// (overrides HTTP_PROXY/HTTPS_PROXY)
+    #[arg(long, global = true, value_name = "URL")]
+    proxy: Option<String>,
+
+    /// Config file holding the default version, aliases, pins and hook;
+    /// history is kept next to it (overrides GVM_CONFIG)
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Shown in `--help` for commands that talk to go.dev
+const NETWORK_HELP: &str = "\
+Requests to go.dev give up after 10s connecting or 30s without data,
+and are retried twice with backoff. They go through HTTP_PROXY/HTTPS_PROXY
+(respecting NO_PROXY) unless --proxy is given.";
+
+/// Shown in `--help` for commands that work with the bin directory
+const BIN_DIR_HELP: &str = "\
+Go version wrappers and the `go` symlink live in the bin directory:
+  1. $GVM_HOME/bin, if GVM_HOME is set
+  2. ~/go/bin otherwise";
+
 #[derive(Subcommand)]
 enum Commands {
     /// List all installed Go versions
-    List,
+    #[command(visible_alias = "ls", after_help = BIN_DIR_HELP)]
+    List(ListArgs),
     /// List all available Go versions from go.dev
-    ListAll,
+    #[command(visible_alias = "ls-remote", after_help = NETWORK_HELP)]
+    ListAll(ListAllArgs),
     /// Install a specific Go version
+    #[command(visible_alias = "i", after_help = BIN_DIR_HELP)]
     Install {
-        /// Version to install (e.g., 1.22.11 or go1.22.11)
+        /// Versions to install (e.g., 1.22.11 or go1.22.11).
+        /// Use a minor line like 1.22 for its latest patch, or "latest"
+        #[arg(required_unless_present_any = ["latest", "from_file"])]
+        versions: Vec<String>,
+        /// Install the newest stable release
+        #[arg(long, conflicts_with = "versions")]
+        latest: bool,
+        /// Let "latest" pick a beta or release candidate when it's the newest
+        /// release
+        #[arg(long, conflicts_with = "from_file")]
+        include_unstable: bool,
+        /// Install from a downloaded archive such as go1.22.5.linux-amd64.tar.gz,
+        /// without network access
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["versions", "latest"])]
+        from_file: Option<PathBuf>,
+        /// Version of the --from-file archive, if its name doesn't say
+        #[arg(
+            long = "version",
+            value_name = "VERSION",
+            requires = "from_file",
+            conflicts_with_all = ["versions", "latest"]
+        )]
+        file_version: Option<String>,
+        /// Switch to the version once it's installed (the last one, when
+        /// installing several)
+        #[arg(long = "use", conflicts_with = "from_file")]
+        use_after: bool,
+        /// Only check the installed SDKs against their published checksums,
+        /// like `gvm verify`, without downloading or changing anything
+        #[arg(long, conflicts_with_all = ["from_file", "latest", "use_after"])]
+        checksum_only: bool,
+        #[command(flatten)]
+        options: InstallArgs,
+    },
+    /// Remove a version's wrapper and SDK and install it again from scratch
+    #[command(after_help = BIN_DIR_HELP)]
+    Reinstall {
+        /// Version to reinstall (e.g., 1.22.11 or go1.22.11)
         version: String,
     },
     /// Use a specific Go version
+    #[command(after_help = BIN_DIR_HELP)]
     Use {
-        /// Version to use (e.g., 1.22.11 or go1.22.11)
+        /// Version to use (e.g., 1.22.11 or go1.22.11), or "system" for the
+        /// Go outside gvm. Defaults to the nearest .go-version file
+        version: Option<String>,
+        /// Use the toolchain or go directive from the nearest go.mod
+        #[arg(long, conflicts_with = "version")]
+        from_gomod: bool,
+        /// Print commands that switch only the current shell, for
+        /// eval "$(gvm use <version> --shell)", instead of moving the symlink
+        #[arg(long, visible_alias = "temporary")]
+        shell: bool,
+        /// Remember an environment variable for this version, which gvm env,
+        /// exec and run then set while it's in use (e.g. GOEXPERIMENT=rangefunc)
+        #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_env_assignment)]
+        set_env: Vec<(String, String)>,
+        /// Forget a variable remembered with --set
+        #[arg(long = "unset", value_name = "KEY")]
+        unset_env: Vec<String>,
+    },
+    /// Define an alias for a version, or show the defined aliases
+    Alias {
+        /// Alias name (e.g., lts). "latest" and "system" are built in.
+        /// Lists every alias when omitted
+        name: Option<String>,
+        /// Version the alias stands for (e.g., 1.22.11 or 1.22).
+        /// Shows the alias when omitted
+        version: Option<String>,
+        /// Remove the alias instead of defining it
+        #[arg(long, requires = "name", conflicts_with = "version")]
+        delete: bool,
+    },
+    /// Set the default Go version, or re-apply the saved default
+    Default {
+        /// Version to make the default (e.g., 1.22.11 or go1.22.11)
+        version: Option<String>,
+    },
+    /// Print the shell commands that put gvm's bin directory on PATH and
+    /// point GOROOT at the current version's SDK
+    #[command(after_help = BIN_DIR_HELP)]
+    Env {
+        /// Shell to print for (defaults to the one in $SHELL)
+        #[arg(long, value_enum)]
+        shell: Option<EnvShell>,
+    },
+    /// Print a shell hook that switches this shell to the version in the
+    /// nearest .go-version whenever it changes (add to your shell profile)
+    Hook {
+        shell: HookShell,
+    },
+    /// Print a completion script for your shell; versions are completed
+    /// from the ones installed (add to your shell profile)
+    Completions {
+        shell: HookShell,
+    },
+    /// Print installed version numbers, one per line (used by completions)
+    #[command(name = "__complete_versions", hide = true)]
+    CompleteVersions,
+    /// Print the path to an installed version's go binary
+    #[command(after_help = BIN_DIR_HELP)]
+    Which {
+        /// Version to look up (e.g., 1.22.11 or go1.22.11)
+        version: String,
+        /// Print the version's GOROOT (its SDK root) instead
+        #[arg(long)]
+        goroot: bool,
+    },
+    /// Run a specific Go version without switching to it
+    #[command(after_help = BIN_DIR_HELP)]
+    Run {
+        /// Version to run (e.g., 1.22.11 or go1.22.11)
+        version: String,
+        /// Arguments passed to go (e.g., gvm run 1.22.11 -- build ./...)
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Run any command with gvm's bin directory at the front of PATH
+    #[command(after_help = BIN_DIR_HELP)]
+    Exec {
+        /// Command and its arguments (e.g., gvm exec -- make build)
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// Print the currently active Go version
+    Current,
+    /// Summarize the current and installed versions, the newest stable
+    /// release, and whether gvm is set up on PATH
+    #[command(after_help = BIN_DIR_HELP)]
+    Status,
+    /// Uninstall a specific Go version
+    #[command(after_help = BIN_DIR_HELP)]
+    Uninstall {
+        /// Version to uninstall (e.g., 1.22.11 or go1.22.11)
+        #[arg(required_unless_present = "minor")]
+        version: Option<String>,
+        /// Remove every installed patch of a minor line (e.g. 1.20)
+        #[arg(long, value_name = "LINE", conflicts_with = "version")]
+        minor: Option<String>,
+        /// Remove the version even if it is currently in use
+        #[arg(long)]
+        force: bool,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// Remove the SDK downloaded for another operating system with
+        /// `gvm install --os` instead
+        #[arg(long, conflicts_with = "minor")]
+        os: Option<String>,
+        /// Remove the SDK downloaded for another architecture with
+        /// `gvm install --arch` instead
+        #[arg(long, conflicts_with = "minor")]
+        arch: Option<String>,
+    },
+    /// Update gvm itself to the newest release on GitHub
+    #[command(after_help = NETWORK_HELP)]
+    SelfUpdate {
+        /// Only report whether a newer release is available
+        #[arg(long)]
+        check_only: bool,
+    },
+    /// Check for common setup problems
+    #[command(after_help = BIN_DIR_HELP)]
+    Doctor,
+    /// Check installed SDKs against the checksums go.dev publishes, to
+    /// catch downloads corrupted on disk
+    Verify {
+        /// Version to verify (e.g., 1.22.11 or go1.22.11)
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        version: Option<String>,
+        /// Verify every installed version
+        #[arg(long)]
+        all: bool,
+    },
+    /// Protect an installed version from `gvm prune`
+    #[command(after_help = BIN_DIR_HELP)]
+    Pin {
+        /// Version to pin (e.g., 1.22.11 or go1.22.11)
+        version: String,
+    },
+    /// Let `gvm prune` remove a pinned version again
+    Unpin {
+        /// Version to unpin (e.g., 1.22.11 or go1.22.11)
         version: String,
     },
+    /// Remove all but the newest installed versions
+    #[command(after_help = BIN_DIR_HELP)]
+    Prune {
+        /// Number of newest versions to keep (the current and pinned versions
+        /// are always kept)
+        #[arg(long)]
+        keep: usize,
+        /// Show what would be removed without removing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Give gvm's managed link another name than `go` (e.g. gvm-go), to
+    /// leave `go` to asdf or goenv; `gvm rename-link go` restores it
+    #[command(after_help = BIN_DIR_HELP)]
+    RenameLink {
+        /// New name for the link
+        name: String,
+    },
+    /// Clear gvm's release list cache, and optionally what installs left in
+    /// Go's module cache and SDKs without a wrapper
+    #[command(after_help = BIN_DIR_HELP)]
+    Clean {
+        /// Also remove the golang.org/dl wrapper modules `go install` put in
+        /// the Go module cache
+        #[arg(long)]
+        modules: bool,
+        /// Everything --modules removes, plus host SDKs in ~/sdk whose
+        /// wrapper is gone
+        #[arg(long)]
+        all: bool,
+        /// Don't ask before removing SDKs
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Show recent `use` and `default` switches
+    History {
+        /// Number of switches to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Copy, ValueEnum)]
+enum HookShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum EnvShell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+impl EnvShell {
+    /// The shell named by `$SHELL`, falling back to PowerShell on Windows
+    /// (where it's usually unset) and bash syntax elsewhere
+    fn detect() -> Self {
+        match detect_shell().trim_end_matches(".exe") {
+            "fish" => EnvShell::Fish,
+            "zsh" => EnvShell::Zsh,
+            "pwsh" | "powershell" => EnvShell::Powershell,
+            _ if cfg!(windows) && std::env::var_os("SHELL").is_none() => EnvShell::Powershell,
+            _ => EnvShell::Bash,
+        }
+    }
+}
+
+#[derive(Args)]
+struct ListArgs {
+    /// Show how much disk space each version's SDK takes up
+    #[arg(long)]
+    size: bool,
+    /// Print each version with a template instead of the usual output.
+    /// Placeholders: {version}, {current} ("*" for the active version),
+    /// {platform} (e.g. linux/amd64) and {path} (the version's go binary)
+    #[arg(long, value_name = "TEMPLATE", conflicts_with = "size")]
+    format: Option<String>,
+    /// Also check that each wrapper has its own SDK of the version it's
+    /// named for
+    #[arg(long)]
+    verify: bool,
+}
+
+#[derive(Args)]
+struct ListAllArgs {
+    /// Ignore the cached release list and fetch it again from go.dev
+    #[arg(long)]
+    refresh: bool,
+    /// Show every available version
+    #[arg(long, conflicts_with = "limit")]
+    all: bool,
+    /// Number of versions to show
+    #[arg(long, default_value_t = 30)]
+    limit: usize,
+    /// Only show stable releases
+    #[arg(long)]
+    stable_only: bool,
+    /// Only show the newest release of each minor line (e.g. 1.22)
+    #[arg(long)]
+    by_minor: bool,
+    /// Only show versions containing this text (e.g. 1.19), searching the
+    /// whole release list before --limit applies
+    #[arg(long, value_name = "SUBSTR")]
+    search: Option<String>,
+    /// Check installed versions against go.dev: flag newer patches and
+    /// releases that are no longer listed
+    #[arg(long, conflicts_with_all = ["all", "limit", "by_minor", "stable_only", "search"])]
+    installed_only: bool,
+    /// For each installed minor line, show the installed patches and the
+    /// newer ones that aren't installed
+    #[arg(
+        long,
+        conflicts_with_all = ["all", "limit", "by_minor", "stable_only", "search", "installed_only"]
+    )]
+    gaps: bool,
+    /// Show roughly how recent each release is. go.dev publishes no release
+    /// dates, so this counts the releases listed ahead of it
+    #[arg(long)]
+    dates: bool,
+}
+
+#[derive(Args, Default)]
+struct InstallArgs {
+    /// Hide output from `go install` and the SDK download unless they fail;
+    /// set by the global `--quiet`
+    #[arg(skip)]
+    quiet: bool,
+    /// Only install the `goX.X.X` wrapper; a later `gvm install` of the same
+    /// version downloads its SDK
+    #[arg(long, conflicts_with_all = ["os", "arch"], overrides_with = "no_only_wrapper")]
+    only_wrapper: bool,
+    /// Install the SDK too, even if the default install flags say
+    /// `--only-wrapper`
+    #[arg(long, overrides_with = "only_wrapper")]
+    no_only_wrapper: bool,
+    /// Exit quietly if the version is already installed, for re-runnable CI
+    /// steps
+    #[arg(long, overrides_with = "no_skip_if_present")]
+    skip_if_present: bool,
+    /// Install even if the default install flags say `--skip-if-present`
+    #[arg(long, overrides_with = "skip_if_present")]
+    no_skip_if_present: bool,
+    /// Print what would be run and where files would go, without running
+    /// or writing anything
+    #[arg(long, overrides_with = "no_dry_run")]
+    dry_run: bool,
+    /// Really install, even if the default install flags say `--dry-run`
+    #[arg(long, overrides_with = "dry_run")]
+    no_dry_run: bool,
+    /// Give up on the SDK download after this many seconds, removing what
+    /// was downloaded so far
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<u64>,
+    /// Download the SDK for another operating system (GOOS), e.g. darwin
+    #[arg(long)]
+    os: Option<String>,
+    /// Download the SDK for another architecture (GOARCH), e.g. arm64
+    #[arg(long)]
+    arch: Option<String>,
+}
+
+impl InstallArgs {
+    /// These options with anything not given on the command line filled in
+    /// from the default install flags
+    fn with_defaults(self) -> Self {
+        let options = self.merge(&default_install_flags().options);
+        if let Err(e) = options.check_conflicts() {
+            fatal(&format!("{} (with the default install flags)", e));
+        }
+        options
+    }
+
+    /// Options given here win, and a `--no-…` switch here turns off the
+    /// same switch in `defaults`
+    fn merge(self, defaults: &InstallArgs) -> Self {
+        let switch = |given: bool, off: bool, default: bool| given || (default && !off);
+        InstallArgs {
+            quiet: self.quiet,
+            only_wrapper: switch(self.only_wrapper, self.no_only_wrapper, defaults.only_wrapper),
+            skip_if_present: switch(
+                self.skip_if_present,
+                self.no_skip_if_present,
+                defaults.skip_if_present,
+            ),
+            dry_run: switch(self.dry_run, self.no_dry_run, defaults.dry_run),
+            timeout: self.timeout.or(defaults.timeout),
+            os: self.os.or_else(|| defaults.os.clone()),
+            arch: self.arch.or_else(|| defaults.arch.clone()),
+            ..InstallArgs::default()
+        }
+    }
+
+    /// clap's `conflicts_with` for options that were merged after parsing
+    fn check_conflicts(&self) -> Result<(), String> {
+        if self.only_wrapper && (self.os.is_some() || self.arch.is_some()) {
+            return Err("--only-wrapper can't be used with --os or --arch".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// `GVM_DEFAULT_INSTALL_FLAGS` or the config's `default_install_flags`: the
+/// install options plus the mirror and proxy every install starts from
+#[derive(Parser, Default)]
+#[command(name = "default install flags", no_binary_name = true)]
+struct DefaultInstallFlags {
+    #[command(flatten)]
+    options: InstallArgs,
+    #[arg(long, value_name = "URL")]
+    base_url: Option<String>,
+    #[arg(long, value_name = "URL")]
+    proxy: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 struct GoRelease {
     version: String,
+    #[serde(default)]
     stable: bool,
+    #[serde(default)]
+    files: Vec<GoFile>,
 }
 
-fn get_go_bin_dir() -> PathBuf {
-    dirs::home_dir()
-        .expect("Could not find home directory")
-        .join("go")
-        .join("bin")
+/// A downloadable file of a release, as listed by go.dev
+#[derive(Debug, Deserialize, Serialize)]
+struct GoFile {
+    filename: String,
+    os: String,
+    arch: String,
+    sha256: String,
+    kind: String,
 }
 
-fn normalize_version(version: &str) -> String {
-    if version.starts_with("go") {
-        version.to_string()
-    } else {
-        format!("go{}", version)
-    }
+/// gvm's persistent settings, stored in `config.toml`
+#[derive(Default, Deserialize, Serialize)]
+struct Config {
+    /// Version re-applied by `gvm default`
+    default: Option<String>,
+    /// Version `gvm use` last switched to, which tells the current version
+    /// when `go` is a regular file rather than a symlink
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    current: Option<String>,
+    /// Names defined with `gvm alias`, mapped to the versions they stand for
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    aliases: BTreeMap<String, String>,
+    /// Versions `gvm prune` must never remove, set with `gvm pin`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pinned: Vec<String>,
+    /// Command run after each `gvm use`; `GVM_POST_USE_HOOK` overrides it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    post_use_hook: Option<String>,
+    /// Flags every `gvm install` starts from, e.g. `--timeout 600`;
+    /// `GVM_DEFAULT_INSTALL_FLAGS` overrides it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default_install_flags: Option<String>,
+    /// Environment variables set for a version while it's in use, keyed by
+    /// version number and recorded with `gvm use --set`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    env: BTreeMap<String, BTreeMap<String, String>>,
+    /// Name of the managed link in place of `go`, set with `gvm rename-link`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    link_name: Option<String>,
 }
 
-fn extract_version_number(version: &str) -> &str {
-    version.strip_prefix("go").unwrap_or(version)
+/// A release of gvm itself, as listed by the GitHub API
+#[derive(Debug, Deserialize)]
+struct GvmRelease {
+    tag_name: String,
+    #[serde(default)]
+    assets: Vec<GvmAsset>,
 }
 
-fn list_installed_versions() -> Vec<String> {
-    let bin_dir = get_go_bin_dir();
+#[derive(Debug, Deserialize)]
+struct GvmAsset {
+    name: String,
+    browser_download_url: String,
+}
 
-    if !bin_dir.exists() {
-        return Vec::new();
-    }
+/// A version whose wrapper is installed but whose SDK is still to be downloaded
+struct PendingDownload {
+    normalized: String,
+    go_wrapper: PathBuf,
+    /// Kill `goX.X.X download` if it runs longer than this
+    timeout: Option<Duration>,
+}
 
-    let mut versions: Vec<String> = fs::read_dir(&bin_dir)
-        .unwrap_or_else(|_| panic!("Failed to read directory: {:?}", bin_dir))
-        .filter_map(|entry| {
-            let entry = entry.ok()?;
-            let file_name = entry.file_name().to_string_lossy().to_string();
-            // Match go1.x.x pattern but not just "go"
-            if file_name.starts_with("go1.") && !file_name.contains('.') == false {
-                Some(file_name)
-            } else {
-                None
-            }
-        })
-        .collect();
+/// Outcome of checking a downloaded SDK against its published checksum
+enum Verification {
+    Verified,
+    Skipped(String),
+    Mismatch { expected: String, actual: String },
+}
 
-    versions.sort_by(|a, b| {
-        let a_ver = extract_version_number(a);
-        let b_ver = extract_version_number(b);
-        version_compare(a_ver, b_ver)
-    });
+/// Release list cached on disk, with the Unix time it was fetched
+#[derive(Deserialize, Serialize)]
+struct ReleaseCache {
+    fetched_at: u64,
+    releases: Vec<GoRelease>,
+}
 
-    versions
+const DEFAULT_DOWNLOAD_BASE: &str = "https://go.dev/dl/";
+
+/// Where `gvm self-update` looks for new releases of gvm
+const GVM_LATEST_RELEASE_URL: &str = "https://api.github.com/repos/Yiheng-Liu/gvm/releases/latest";
+
+/// How long a cached release list is reused before fetching again
+const RELEASE_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How often `gvm list` may go to the network to look for a newer Go
+const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Serialize)]
+struct ListEntry {
+    version: String,
+    current: bool,
+    pinned: bool,
+    /// GOOS/GOARCH the SDK was built for, e.g. `linux/amd64`
+    platform: Option<String>,
+    /// Missing for SDKs downloaded for another platform with `--os`/`--arch`
+    wrapper: Option<PathBuf>,
+    /// Missing when the SDK was never downloaded or has been deleted
+    sdk_root: Option<PathBuf>,
+    /// Bytes used by the SDK
+    size: u64,
 }
 
-fn version_compare(a: &str, b: &str) -> std::cmp::Ordering {
-    let parse_version = |s: &str| -> (u32, u32, u32) {
-        let parts: Vec<&str> = s.split('.').collect();
-        let major = parts.first().and_then(|p| p.parse().ok()).unwrap_or(0);
-        let minor = parts.get(1).and_then(|p| p.parse().ok()).unwrap_or(0);
-        let patch = parts.get(2).and_then(|p| p.parse().ok()).unwrap_or(0);
-        (major, minor, patch)
-    };
+#[derive(Serialize)]
+struct ListAllOutput {
+    versions: Vec<ListAllEntry>,
+    total: usize,
+}
 
-    let a_parsed = parse_version(a);
-    let b_parsed = parse_version(b);
-    a_parsed.cmp(&b_parsed)
+#[derive(Serialize)]
+struct ListAllEntry {
+    version: String,
+    stable: bool,
+    installed: bool,
+    /// With `--dates`: how many releases go.dev lists ahead of this one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    newer_releases: Option<usize>,
 }
 
-fn get_current_version() -> Option<String> {
-    let bin_dir = get_go_bin_dir();
-    let go_link = bin_dir.join("go");
+#[derive(Serialize)]
+struct InstalledCheckEntry {
+    version: String,
+    listed: bool,
+    newer_patch: Option<String>,
+}
 
-    if go_link.is_symlink() {
-        if let Ok(target) = fs::read_link(&go_link) {
-            let target_name = target
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string());
-            return target_name;
+#[derive(Serialize)]
+struct MinorLineGaps {
+    line: String,
+    installed: Vec<InstalledPatch>,
+    missing: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct InstalledPatch {
+    version: String,
+    newer_available: usize,
+}
+
+/// One line of the switch history
+#[derive(Serialize)]
+struct HistoryEntry {
+    timestamp: u64,
+    command: String,
+    version: String,
+    previous: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CurrentOutput {
+    current: Option<String>,
+}
+
+#[derive(Serialize)]
+struct StatusOutput {
+    current: Option<String>,
+    installed: usize,
+    /// `None` when the release list couldn't be fetched
+    latest_stable: Option<String>,
+    link_dir: PathBuf,
+    link_dir_on_path: bool,
+    config_file: PathBuf,
+}
+
+/// Why a command failed. The error has already been shown to the user by
+/// the time this is returned; it only decides gvm's exit status.
+#[derive(Debug)]
+enum Failure {
+    /// Exit code 1: any failure not covered below
+    Failed,
+    /// Exit code 3: the requested version isn't installed
+    NotInstalled,
+    /// Exit code 4: go.dev or the download mirror couldn't be reached or read
+    Network,
+    /// A child process's own exit code, passed through by `gvm run` and `gvm exec`
+    Child(i32),
+}
+
+impl Failure {
+    fn code(&self) -> u8 {
+        match self {
+            Failure::Failed => 1,
+            Failure::NotInstalled => 3,
+            Failure::Network => 4,
+            Failure::Child(code) => u8::try_from(*code).unwrap_or(1),
+        }
+    }
+
+    fn exit_code(&self) -> ExitCode {
+        ExitCode::from(self.code())
+    }
+
+    /// Name used for the failure in `--json` error output
+    fn kind(&self) -> &'static str {
+        match self {
+            Failure::Failed => "failed",
+            Failure::NotInstalled => "not_installed",
+            Failure::Network => "network",
+            Failure::Child(_) => "child",
         }
     }
-    None
 }
 
-fn cmd_list() {
-    let versions = list_installed_versions();
-    let current = get_current_version();
+type CmdResult = Result<(), Failure>;
 
-    if versions.is_empty() {
-        println!("{}", "No Go versions installed.".yellow());
-        println!(
-            "Use {} to install a version.",
-            "gvm install <version>".green()
-        );
-        return;
+/// Report a failed request to go.dev
+fn network_failure(message: String) -> Failure {
+    report!("{} {}", "Error:".red().bold(), message);
+    Failure::Network
+}
+
+/// Refuse a command that needs go.dev in a build without the `network`
+/// feature
+fn require_network(command: &str) -> CmdResult {
+    if cfg!(feature = "network") {
+        return Ok(());
     }
+    report!(
+        "{} gvm was built without network support, so `gvm {}` is unavailable.",
+        "Error:".red().bold(),
+        command
+    );
+    report!("Reinstall gvm with its default features to enable it.");
+    Err(Failure::Failed)
+}
 
-    println!("{}", "Installed Go versions:".bold());
-    for version in versions {
-        let version_num = extract_version_number(&version);
-        if Some(version.clone()) == current {
-            println!("  {} {} {}", "->".green().bold(), version_num.green().bold(), "(current)".dimmed());
-        } else {
-            println!("     {}", version_num);
-        }
+fn print_json<T: Serialize>(value: &T) {
+    match serde_json::to_string(value) {
+        Ok(json) => println!("{}", json),
+        Err(e) => report!("{} Failed to serialize output: {}", "Error:".red().bold(), e),
     }
 }
 
-fn cmd_list_all() {
-    println!("{}", "Fetching available Go versions...".dimmed());
+/// Print an error and exit non-zero, for failures gvm can't continue past
+fn fatal(message: &str) -> ! {
+    report!("{} {}", "Error:".red().bold(), message);
+    flush_json_reports(Err(&Failure::Failed));
+    std::process::exit(1);
+}
 
-    let url = "https://go.dev/dl/?mode=json&include=all";
+/// Set by `--json`: stderr messages are held for the JSON error output
+static JSON_REPORTS: AtomicBool = AtomicBool::new(false);
 
-    let response = reqwest::blocking::get(url);
+static REPORTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
 
-    match response {
-        Ok(resp) => {
-            if !resp.status().is_success() {
-                eprintln!("{} Failed to fetch versions: HTTP {}", "Error:".red().bold(), resp.status());
-                return;
-            }
+fn report_line(line: String) {
+    if JSON_REPORTS.load(Ordering::Relaxed) {
+        REPORTED.lock().unwrap().push(line);
+    } else {
+        eprintln!("{}", line);
+    }
+}
 
-            match resp.json::<Vec<GoRelease>>() {
-                Ok(releases) => {
-                    let mut versions: Vec<_> = releases
-                        .iter()
-                        .map(|r| {
-                            let version_num = extract_version_number(&r.version);
-                            (version_num.to_string(), r.stable)
-                        })
-                        .collect();
-
-                    // Remove duplicates and sort
-                    versions.dedup_by(|a, b| a.0 == b.0);
-                    versions.sort_by(|a, b| version_compare(&b.0, &a.0));
-
-                    let installed = list_installed_versions();
-                    let installed_nums: Vec<_> = installed
-                        .iter()
-                        .map(|v| extract_version_number(v).to_string())
-                        .collect();
-
-                    println!("{}", "Available Go versions:".bold());
-                    println!("{}", "(stable versions marked with *, installed versions marked with ✓)".dimmed());
-                    println!();
-
-                    // Show latest 30 versions by default
-                    for (version, stable) in versions.iter().take(30) {
-                        let is_installed = installed_nums.contains(version);
-                        let stable_marker = if *stable { "*" } else { " " };
-                        let install_marker = if is_installed {
-                            "✓".green().to_string()
-                        } else {
-                            " ".to_string()
-                        };
-
-                        if *stable {
-                            println!("  {} {} {}", install_marker, stable_marker.cyan(), version.cyan());
-                        } else {
-                            println!("  {} {} {}", install_marker, stable_marker, version);
-                        }
-                    }
+/// With `--json`, print the collected messages to stderr as one object:
+/// `{"error": ..., "kind": ..., "exit_code": ..., "messages": [...]}` on
+/// failure, or `{"messages": [...]}` if a successful command had warnings
+fn flush_json_reports(result: Result<(), &Failure>) {
+    if !JSON_REPORTS.load(Ordering::Relaxed) {
+        return;
+    }
+    let messages = std::mem::take(&mut *REPORTED.lock().unwrap());
 
-                    println!();
-                    println!(
-                        "{}",
-                        format!("Showing latest 30 of {} versions.", versions.len()).dimmed()
-                    );
-                }
-                Err(e) => {
-                    eprintln!("{} Failed to parse response: {}", "Error:".red().bold(), e);
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!("{} Failed to fetch versions: {}", "Error:".red().bold(), e);
+    let envelope = match result {
+        Ok(()) if messages.is_empty() => return,
+        Ok(()) => serde_json::json!({ "messages": messages }),
+        Err(failure) => {
+            let error = messages
+                .iter()
+                .find_map(|m| m.strip_prefix("Error: "))
+                .or(messages.first().map(String::as_str))
+                .unwrap_or("command failed")
+                .to_string();
+            serde_json::json!({
+                "error": error,
+                "kind": failure.kind(),
+                "exit_code": failure.code(),
+                "messages": messages,
+            })
         }
+    };
+    eprintln!("{}", envelope);
+}
+
+fn load_config() -> Config {
+    try_load_config().unwrap_or_else(|e| fatal(&e))
+}
+
+/// `load_config` for callers that can carry on without the config
+fn try_load_config() -> Result<Config, String> {
+    let path = get_config_file();
+    match fs::read_to_string(&path) {
+        Ok(contents) => toml::from_str(&contents)
+            .map_err(|e| format!("Invalid config file {:?}: {}", path, e)),
+        Err(_) => Ok(Config::default()),
     }
 }
 
-fn cmd_install(version: &str) {
-    let normalized = normalize_version(version);
-    let version_num = extract_version_number(&normalized);
+fn save_config(config: &Config) -> Result<(), String> {
+    let path = get_config_file();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+    }
+    let contents =
+        toml::to_string(config).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
 
-    // Check if already installed
-    let bin_dir = get_go_bin_dir();
-    let go_wrapper = bin_dir.join(&normalized);
+/// Switches kept in the history file; older ones are dropped as new ones
+/// are recorded
+const MAX_HISTORY_ENTRIES: usize = 500;
 
-    if go_wrapper.exists() {
-        println!(
-            "{} Go {} is already installed.",
-            "✓".green().bold(),
-            version_num.green()
-        );
-        println!(
-            "Use {} to switch to this version.",
-            format!("gvm use {}", version_num).cyan()
+/// Append a switch to the history file as a tab-separated line. Failures only
+/// warn, since the switch itself already happened.
+fn record_switch(command: &str, version: &str, previous: Option<&str>) {
+    let path = get_history_file();
+    let mut lines: Vec<String> = fs::read_to_string(&path)
+        .map(|contents| contents.lines().map(str::to_string).collect())
+        .unwrap_or_default();
+    lines.push(format!(
+        "{}\t{}\t{}\t{}",
+        now_secs(),
+        command,
+        version,
+        previous.unwrap_or("")
+    ));
+    let start = lines.len().saturating_sub(MAX_HISTORY_ENTRIES);
+
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, lines[start..].join("\n") + "\n"));
+    if let Err(e) = result {
+        report!(
+            "{} Failed to record history in {:?}: {}",
+            "Warning:".yellow().bold(),
+            path,
+            e
         );
-        return;
     }
+}
 
-    println!(
-        "{} {}",
-        "Installing Go version:".bold(),
-        version_num.green()
-    );
+fn load_history() -> Vec<HistoryEntry> {
+    let contents = fs::read_to_string(get_history_file()).unwrap_or_default();
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            Some(HistoryEntry {
+                timestamp: fields.next()?.parse().ok()?,
+                command: fields.next()?.to_string(),
+                version: fields.next()?.to_string(),
+                previous: fields.next().filter(|p| !p.is_empty()).map(str::to_string),
+            })
+        })
+        .collect()
+}
 
-    // Step 1: go install golang.org/dl/goX.X.X@latest
-    println!("{}", "Step 1/2: Installing Go wrapper...".dimmed());
-    let install_pkg = format!("golang.org/dl/{}@latest", normalized);
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
 
-    let install_result = Command::new("go")
-        .args(["install", &install_pkg])
-        .status();
+/// Format a Unix timestamp as `2024-06-01 14:05 UTC`
+fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let minutes = secs % 86400 / 60;
 
-    match install_result {
-        Ok(status) if status.success() => {
-            println!("{}", "  ✓ Go wrapper installed".green());
-        }
-        Ok(status) => {
-            eprintln!(
-                "{} go install failed with exit code: {:?}",
-                "Error:".red().bold(),
-                status.code()
-            );
-            return;
-        }
-        Err(e) => {
-            eprintln!("{} Failed to run go install: {}", "Error:".red().bold(), e);
-            eprintln!(
-                "{}",
-                "Make sure 'go' is installed and available in your PATH.".yellow()
-            );
-            return;
-        }
-    }
+    // Howard Hinnant's days-to-civil conversion
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
 
-    // Step 2: goX.X.X download
-    println!("{}", "Step 2/2: Downloading Go SDK...".dimmed());
-    let bin_dir = get_go_bin_dir();
-    let go_wrapper = bin_dir.join(&normalized);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
 
-    if !go_wrapper.exists() {
-        eprintln!(
-            "{} Go wrapper not found at {:?}",
-            "Error:".red().bold(),
-            go_wrapper
-        );
-        return;
-    }
+fn load_release_cache() -> Option<ReleaseCache> {
+    let contents = fs::read_to_string(get_release_cache_file()?).ok()?;
+    serde_json::from_str(&contents).ok()
+}
 
-    let download_result = Command::new(&go_wrapper).arg("download").status();
+fn save_release_cache(releases: Vec<GoRelease>) -> Vec<GoRelease> {
+    let cache = ReleaseCache {
+        fetched_at: now_secs(),
+        releases,
+    };
 
-    match download_result {
-        Ok(status) if status.success() => {
-            println!("{}", "  ✓ Go SDK downloaded".green());
-            println!();
-            println!(
-                "{} Go {} installed successfully!",
-                "✓".green().bold(),
-                version_num.green()
-            );
-            println!(
-                "Use {} to switch to this version.",
-                format!("gvm use {}", version_num).cyan()
-            );
+    // Caching is best-effort; a failed write just means fetching again next time
+    if let Some(path) = get_release_cache_file() {
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
         }
-        Ok(status) => {
-            eprintln!(
-                "{} download failed with exit code: {:?}",
-                "Error:".red().bold(),
-                status.code()
-            );
-        }
-        Err(e) => {
-            eprintln!("{} Failed to download Go SDK: {}", "Error:".red().bold(), e);
+        if let Ok(contents) = serde_json::to_string(&cache) {
+            let _ = fs::write(&path, contents);
         }
     }
+
+    cache.releases
 }
 
-fn cmd_use(version: &str) {
-    let normalized = normalize_version(version);
-    let version_num = extract_version_number(&normalized);
+/// Network settings from global flags, set once at startup
+#[derive(Default)]
+struct NetworkOptions {
+    base_url: Option<String>,
+    // Only the HTTP client reads it
+    #[cfg_attr(not(feature = "network"), allow(dead_code))]
+    proxy: Option<String>,
+}
 
-    let bin_dir = get_go_bin_dir();
-    let go_wrapper = bin_dir.join(&normalized);
-    let go_link = bin_dir.join("go");
+/// Set by `--verbose`
+static VERBOSE: AtomicBool = AtomicBool::new(false);
 
-    // Check if version is installed
-    if !go_wrapper.exists() {
-        eprintln!(
-            "{} Go {} is not installed.",
-            "Error:".red().bold(),
-            version_num
-        );
-        eprintln!(
-            "Run {} to install it first.",
-            format!("gvm install {}", version_num).cyan()
-        );
+/// Set by `--quiet`
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Print a `--verbose` diagnostic to stderr
+fn log_verbose(message: &str) {
+    if VERBOSE.load(Ordering::Relaxed) {
+        eprintln!("{} {}", "[verbose]".dimmed(), message);
+    }
+}
+
+/// Log an external command's argument vector, extra environment and working
+/// directory before it runs
+fn log_command(command: &Command) {
+    if !VERBOSE.load(Ordering::Relaxed) {
         return;
     }
+    let argv: Vec<String> = std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| format!("{:?}", arg))
+        .collect();
+    let cwd = command
+        .get_current_dir()
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+    log_verbose(&format!("running [{}] in {}", argv.join(", "), cwd.display()));
+    for (key, value) in command.get_envs() {
+        let value = value.map_or("<removed>".into(), |v| v.to_string_lossy());
+        log_verbose(&format!("  with {}={}", key.to_string_lossy(), value));
+    }
+}
 
-    // Remove existing symlink or file
-    if go_link.exists() || go_link.is_symlink() {
-        if let Err(e) = fs::remove_file(&go_link) {
-            eprintln!(
-                "{} Failed to remove existing 'go': {}",
-                "Error:".red().bold(),
-                e
-            );
-            return;
+static NETWORK_OPTIONS: OnceLock<NetworkOptions> = OnceLock::new();
+
+/// The global network flags, falling back to the default install flags
+fn network_options() -> &'static NetworkOptions {
+    static RESOLVED: OnceLock<NetworkOptions> = OnceLock::new();
+    RESOLVED.get_or_init(|| {
+        let flags = NETWORK_OPTIONS.get_or_init(NetworkOptions::default);
+        let defaults = default_install_flags();
+        NetworkOptions {
+            base_url: flags.base_url.clone().or_else(|| defaults.base_url.clone()),
+            proxy: flags.proxy.clone().or_else(|| defaults.proxy.clone()),
         }
+    })
+}
+
+/// Flags installs start from: `GVM_DEFAULT_INSTALL_FLAGS`, or else the
+/// config's `default_install_flags`, split on whitespace. Read once, and only
+/// by commands that install or go to the network.
+fn default_install_flags() -> &'static DefaultInstallFlags {
+    static DEFAULTS: OnceLock<DefaultInstallFlags> = OnceLock::new();
+    DEFAULTS.get_or_init(|| {
+        let (flags, source) = match std::env::var("GVM_DEFAULT_INSTALL_FLAGS") {
+            Ok(flags) => (flags, "GVM_DEFAULT_INSTALL_FLAGS"),
+            Err(_) => match load_config().default_install_flags {
+                Some(flags) => (flags, "default_install_flags in the config file"),
+                None => return DefaultInstallFlags::default(),
+            },
+        };
+        DefaultInstallFlags::try_parse_from(flags.split_whitespace()).unwrap_or_else(|e| {
+            let error = e.to_string();
+            let reason = error.lines().next().unwrap_or_default();
+            fatal(&format!(
+                "Invalid default install flags {:?} from {}: {}",
+                flags,
+                source,
+                reason.trim_start_matches("error: ")
+            ))
+        })
+    })
+}
+
+/// Where releases are downloaded from: `--base-url`, then `GVM_DOWNLOAD_BASE`,
+/// then go.dev. A mirror must serve the same `?mode=json` index and file names.
+fn download_base() -> String {
+    let base = network_options()
+        .base_url
+        .clone()
+        .or_else(|| std::env::var("GVM_DOWNLOAD_BASE").ok())
+        .filter(|base| !base.is_empty())
+        .unwrap_or_else(|| DEFAULT_DOWNLOAD_BASE.to_string());
+
+    if base.ends_with('/') {
+        base
+    } else {
+        format!("{}/", base)
     }
+}
 
-    // Create new symlink
-    match unix_fs::symlink(&go_wrapper, &go_link) {
-        Ok(_) => {
+/// The release index that SDK checksums are read from: go.dev's, or the
+/// mirror's when one is set
+fn checksum_source() -> String {
+    format!("{}?mode=json", download_base())
+}
+
+fn fetch_releases() -> Result<Vec<GoRelease>, String> {
+    let url = format!("{}?mode=json&include=all", download_base());
+    let body = net::get_text(&url).map_err(|e| format!("Failed to fetch versions: {}", e))?;
+
+    let entries: Vec<serde_json::Value> =
+        serde_json::from_str(&body).map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    // One release in an unexpected shape shouldn't hide all the others
+    let total = entries.len();
+    let releases: Vec<GoRelease> = entries
+        .into_iter()
+        .filter_map(|entry| serde_json::from_value(entry).ok())
+        .collect();
+    if releases.len() < total {
+        log_verbose(&format!(
+            "skipped {} malformed of {} releases in the list",
+            total - releases.len(),
+            total
+        ));
+    }
+    if releases.is_empty() && total > 0 {
+        return Err("Failed to parse response: no release in it could be read".to_string());
+    }
+    Ok(releases)
+}
+
+/// Get the go.dev release list, reusing the on-disk cache while it is fresh
+fn get_releases(refresh: bool, quiet: bool) -> Result<Vec<GoRelease>, String> {
+    if !refresh {
+        if let Some(cache) = load_release_cache() {
+            let age = now_secs().saturating_sub(cache.fetched_at);
+            if age < RELEASE_CACHE_TTL.as_secs() {
+                return Ok(cache.releases);
+            }
+        }
+    }
+
+    if !quiet {
+        println!("{}", "Fetching available Go versions...".dimmed());
+    }
+
+    fetch_releases().map(save_release_cache)
+}
+
+/// Aliases gvm resolves itself, which can't be redefined with `gvm alias`
+const BUILTIN_ALIASES: [&str; 2] = ["latest", "system"];
+
+/// Stable releases in the go.dev list, skipping any gvm can't parse
+fn stable_versions(releases: &[GoRelease]) -> impl Iterator<Item = Version> + '_ {
+    releases
+        .iter()
+        .filter(|r| r.stable)
+        .filter_map(|r| r.version.parse().ok())
+}
+
+/// Newest stable release from go.dev, optionally within a minor line
+fn newest_stable_release(line: Option<&Version>) -> Result<String, Failure> {
+    let releases = get_releases(false, true).map_err(network_failure)?;
+    stable_versions(&releases)
+        .filter(|v| line.is_none_or(|line| v.in_minor_line(line)))
+        .max()
+        .map(|v| v.to_string())
+        .ok_or_else(|| {
+            report!(
+                "{} No stable release found matching {}",
+                "Error:".red().bold(),
+                line.map_or("latest".to_string(), Version::to_string)
+            );
+            Failure::Failed
+        })
+}
+
+/// Newest release from go.dev, betas and release candidates included. Go's
+/// ordering puts `1.24rc1` after `1.23.4` but before `1.24.0`, so a
+/// pre-release only wins until its final release is out.
+fn newest_release() -> Result<String, Failure> {
+    let releases = get_releases(false, true).map_err(network_failure)?;
+    releases
+        .iter()
+        .filter_map(|r| r.version.parse::<Version>().ok())
+        .max()
+        .map(|v| v.to_string())
+        .ok_or_else(|| {
+            report!("{} The release list is empty.", "Error:".red().bold());
+            Failure::Failed
+        })
+}
+
+/// The first `go` on PATH that isn't in gvm's bin directory or gvm's link
+fn find_system_go() -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    let bin_dir = get_go_bin_dir();
+    let go_link = get_go_link();
+    let file_name = format!("go{}", EXE_SUFFIX);
+    std::env::split_paths(&path)
+        .filter(|dir| *dir != bin_dir)
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| *candidate != go_link && candidate.is_file())
+}
+
+/// Version of the non-gvm `go` on PATH, as reported by `go env GOVERSION`
+fn system_go_version() -> Result<String, Failure> {
+    let not_found = || {
+        report!(
+            "{} No Go outside of gvm was found on PATH.",
+            "Error:".red().bold()
+        );
+        Failure::NotInstalled
+    };
+
+    let go = find_system_go().ok_or_else(not_found)?;
+    let mut command = Command::new(&go);
+    command.args(["env", "GOVERSION"]);
+    log_command(&command);
+    let output = command.output().map_err(|_| not_found())?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || version.is_empty() {
+        return Err(not_found());
+    }
+    Ok(extract_version_number(&version).to_string())
+}
+
+/// Whether `name` refers to an alias rather than a literal version
+fn is_alias(name: &str) -> bool {
+    BUILTIN_ALIASES.contains(&name) || load_config().aliases.contains_key(name)
+}
+
+/// Resolve an alias to the version it stands for. `latest` is the newest
+/// stable release and `system` the Go found on PATH outside gvm; anything
+/// that isn't an alias is returned unchanged.
+fn resolve_alias(version: &str) -> Result<String, Failure> {
+    match version {
+        "latest" => newest_stable_release(None),
+        "system" => system_go_version(),
+        _ => Ok(load_config()
+            .aliases
+            .get(version)
+            .cloned()
+            .unwrap_or_else(|| version.to_string())),
+    }
+}
+
+/// Resolve an alias, then a minor line like `1.22` to its newest stable
+/// release from go.dev; anything else is returned unchanged
+fn resolve_install_version(version: &str) -> Result<String, Failure> {
+    let version = resolve_alias(version)?;
+    match version.parse::<Version>() {
+        Ok(line) if line.is_minor_only() => newest_stable_release(Some(&line)),
+        _ => Ok(extract_version_number(&version).to_string()),
+    }
+}
+
+/// The release a mistyped version most likely meant: the newest stable
+/// release in the same minor line, or failing that the newest overall
+fn suggest_version(version: &str, releases: &[GoRelease]) -> Option<String> {
+    // Mistyped input won't parse, so go by its leading `major.minor`
+    let mut parts = extract_version_number(version).split('.');
+    let line = format!("{}.{}", parts.next()?, parts.next().unwrap_or_default());
+    let line = line.parse::<Version>().ok();
+
+    let in_line = stable_versions(releases)
+        .filter(|v| line.as_ref().is_some_and(|line| v.in_minor_line(line)))
+        .max();
+    in_line
+        .or_else(|| stable_versions(releases).max())
+        .map(|v| v.to_string())
+}
+
+/// Check a version number before installing it: it must look like a Go
+/// version and, when the release list can be fetched, be a real release.
+/// Malformed input is rejected without touching the network.
+fn validate_version(version: &str) -> CmdResult {
+    let (message, releases) = if version.parse::<Version>().is_err() {
+        let message = format!("{} is not a valid Go version.", version);
+        (message, load_release_cache().map(|cache| cache.releases))
+    } else {
+        // Without the release list, leave the final word to `go install`
+        let Ok(releases) = get_releases(false, true) else {
+            return Ok(());
+        };
+        if releases
+            .iter()
+            .any(|r| extract_version_number(&r.version) == version)
+        {
+            return Ok(());
+        }
+        (format!("Go {} is not a known release.", version), Some(releases))
+    };
+
+    report!("{} {}", "Error:".red().bold(), message);
+    if let Some(suggestion) = releases.and_then(|releases| suggest_version(version, &releases)) {
+        report!("Did you mean {}?", suggestion.cyan());
+    }
+    report!(
+        "Versions look like 1.22.11, 1.22 or 1.23rc1; run {} to see them all.",
+        "gvm list-all".cyan()
+    );
+    Err(Failure::Failed)
+}
+
+/// `sdk::list_installed_versions`, giving up if the bin directory can't be read
+fn list_installed_versions() -> Vec<String> {
+    sdk::list_installed_versions().unwrap_or_else(|e| {
+        fatal(&format!(
+            "Failed to read directory {:?}: {}",
+            get_go_bin_dir(),
+            e
+        ))
+    })
+}
+
+fn get_current_version() -> Option<String> {
+    let go_link = get_go_link();
+
+    if go_link.is_symlink() {
+        if let Ok(target) = fs::read_link(&go_link) {
+            let target_name = target.file_name().map(|n| {
+                let name = n.to_string_lossy();
+                name.strip_suffix(EXE_SUFFIX).unwrap_or(&name).to_string()
+            });
+            return target_name;
+        }
+    }
+
+    // On Windows `go.exe` may be a copy of the wrapper rather than a symlink
+    #[cfg(windows)]
+    if go_link.is_file() {
+        let copied = list_installed_versions()
+            .into_iter()
+            .find(|v| files_identical(&get_go_wrapper(v), &go_link));
+        if copied.is_some() {
+            return copied;
+        }
+    }
+
+    // Otherwise a regular file there stands for the version last switched to
+    if go_link.is_file() {
+        return load_config()
+            .current
+            .filter(|version| get_go_wrapper(version).exists());
+    }
+
+    None
+}
+
+/// Remember the version the `go` link now points at, or that there's none
+fn save_current_version(normalized: Option<&str>) {
+    let mut config = load_config();
+    if config.current.as_deref() == normalized {
+        return;
+    }
+    config.current = normalized.map(str::to_string);
+    if let Err(e) = save_config(&config) {
+        report!("{} {}", "Warning:".yellow().bold(), e);
+    }
+}
+
+#[cfg(windows)]
+fn files_identical(a: &Path, b: &Path) -> bool {
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a_meta), Ok(b_meta)) if a_meta.len() == b_meta.len() => {}
+        _ => return false,
+    }
+    match (fs::read(a), fs::read(b)) {
+        (Ok(a_bytes), Ok(b_bytes)) => a_bytes == b_bytes,
+        _ => false,
+    }
+}
+
+/// Total size of the files under `path`, without following symlinks
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Format a byte count like `245.3 MB`
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// An installed SDK as shown by `gvm list`
+struct InstalledSdk {
+    /// Wrapper name (`go1.22.5`) for this machine's SDKs; `None` for ones
+    /// cross-downloaded for another platform
+    normalized: Option<String>,
+    version_num: String,
+    platform: Option<String>,
+    sdk_root: PathBuf,
+}
+
+/// Versions with a wrapper, followed by SDKs downloaded for other platforms.
+/// The wrappers' platforms are only looked up `with_platform`.
+fn list_installed_sdks(with_platform: bool) -> Vec<InstalledSdk> {
+    let mut sdks: Vec<_> = list_installed_versions()
+        .into_iter()
+        .map(|v| InstalledSdk {
+            version_num: extract_version_number(&v).to_string(),
+            platform: if with_platform { go_platform(&v) } else { None },
+            sdk_root: get_sdk_dir().join(&v),
+            normalized: Some(v),
+        })
+        .collect();
+
+    let mut cross: Vec<_> = fs::read_dir(get_sdk_dir())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let (wrapper, platform) = name.rsplit_once('.')?;
+            let version = wrapper_version(wrapper)?;
+            let (os, arch) = platform.split_once('-')?;
+            let sdk_root = entry.path();
+            sdk_root.join(".unpacked-success").exists().then(|| InstalledSdk {
+                normalized: None,
+                version_num: version.to_string(),
+                platform: Some(format!("{}/{}", os, arch)),
+                sdk_root,
+            })
+        })
+        .collect();
+    cross.sort_by(|a, b| version_order(&a.version_num, &b.version_num));
+
+    sdks.append(&mut cross);
+    sdks
+}
+
+/// GOOS/GOARCH an installed version's SDK was built for. Every SDK ships its
+/// tools in `pkg/tool/<goos>_<goarch>`, so that's read instead of starting
+/// the toolchain; only when there isn't exactly one such directory does this
+/// fall back to the wrapper's `go env GOHOSTOS GOHOSTARCH` (not GOOS/GOARCH,
+/// which follow the environment).
+fn go_platform(normalized: &str) -> Option<String> {
+    let tools = get_sdk_dir().join(normalized).join("pkg").join("tool");
+    let mut platforms = fs::read_dir(tools).into_iter().flatten().filter_map(|entry| {
+        let entry = entry.ok()?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let (os, arch) = name.split_once('_')?;
+        entry.path().is_dir().then(|| format!("{}/{}", os, arch))
+    });
+    match (platforms.next(), platforms.next()) {
+        (Some(platform), None) => Some(platform),
+        _ => go_host_platform(&get_go_wrapper(normalized)),
+    }
+}
+
+/// `GOHOSTOS/GOHOSTARCH` of the toolchain behind a `go` binary
+fn go_host_platform(go: &Path) -> Option<String> {
+    let mut command = Command::new(go);
+    command.args(["env", "GOHOSTOS", "GOHOSTARCH"]);
+    log_command(&command);
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines().map(str::trim);
+    match (lines.next(), lines.next()) {
+        (Some(os), Some(arch)) if !os.is_empty() && !arch.is_empty() => {
+            Some(format!("{}/{}", os, arch))
+        }
+        _ => None,
+    }
+}
+
+fn cmd_list(json: bool, args: &ListArgs) -> CmdResult {
+    let with_platform = json || args.format.as_deref().is_none_or(|t| t.contains("{platform}"));
+    let sdks = list_installed_sdks(with_platform);
+    let dangling = is_go_link_dangling();
+    let current = get_current_version().filter(|_| !dangling);
+    let is_current = |sdk: &InstalledSdk| sdk.normalized.is_some() && sdk.normalized == current;
+
+    // On stderr (or in the --json envelope), so it's safe for scripts too
+    if dangling {
+        warn_dangling_go_link();
+    }
+
+    if json {
+        let pinned = load_config().pinned;
+        let entries: Vec<_> = sdks
+            .iter()
+            .map(|sdk| {
+                let sdk_root = Some(sdk.sdk_root.clone()).filter(|dir| dir.is_dir());
+                ListEntry {
+                    current: is_current(sdk),
+                    pinned: sdk.normalized.is_some() && pinned.contains(&sdk.version_num),
+                    platform: sdk.platform.clone(),
+                    wrapper: sdk.normalized.as_deref().map(get_go_wrapper),
+                    size: sdk_root.as_deref().map_or(0, dir_size),
+                    sdk_root,
+                    version: sdk.version_num.clone(),
+                }
+            })
+            .collect();
+        print_json(&entries);
+        return Ok(());
+    }
+
+    if let Some(template) = &args.format {
+        for sdk in &sdks {
+            let path = match &sdk.normalized {
+                Some(normalized) => get_go_wrapper(normalized),
+                None => sdk.sdk_root.clone(),
+            };
+            let line = template
+                .replace("{version}", &sdk.version_num)
+                .replace("{current}", if is_current(sdk) { "*" } else { "" })
+                .replace("{platform}", sdk.platform.as_deref().unwrap_or(""))
+                .replace("{path}", &path.to_string_lossy());
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    if sdks.is_empty() {
+        println!("{}", "No Go versions installed.".yellow());
+        println!(
+            "Use {} to install a version.",
+            "gvm install <version>".green()
+        );
+        return Ok(());
+    }
+
+    println!("{}", "Installed Go versions:".bold());
+    let pinned = load_config().pinned;
+    let mut total = 0;
+    for sdk in &sdks {
+        let version_num = &sdk.version_num;
+        let platform = match (&sdk.platform, &sdk.normalized) {
+            (Some(platform), Some(_)) => format!(" ({})", platform).dimmed().to_string(),
+            (Some(platform), None) => format!(" ({}, SDK only)", platform).dimmed().to_string(),
+            (None, _) => String::new(),
+        };
+        let pin = if sdk.normalized.is_some() && pinned.iter().any(|p| p == version_num) {
+            format!(" {}", "(pinned)".cyan())
+        } else {
+            String::new()
+        };
+        let size = if args.size {
+            let bytes = dir_size(&sdk.sdk_root);
+            total += bytes;
+            format!(" {}", format_size(bytes)).dimmed().to_string()
+        } else {
+            String::new()
+        };
+
+        if is_current(sdk) {
+            println!(
+                "  {} {}{} {}{}{}",
+                "->".green().bold(),
+                version_num.green().bold(),
+                platform,
+                "(current)".dimmed(),
+                pin,
+                size
+            );
+        } else {
+            println!("     {}{}{}{}", version_num, platform, pin, size);
+        }
+    }
+
+    if args.size {
+        println!();
+        println!("{}", format!("Total: {}", format_size(total)).dimmed());
+    }
+
+    if let Some(notice) = current.as_deref().and_then(newer_stable_notice) {
+        println!();
+        println!("{}", notice);
+    }
+    if args.verify {
+        return report_sdk_problems();
+    }
+    Ok(())
+}
+
+/// Wrappers whose SDK doesn't belong to them: two wrappers sharing one
+/// GOROOT, or an SDK whose VERSION file names another release. Either means
+/// a `goX.X.X download` went wrong somewhere.
+fn sdk_problems() -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut roots: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+    for normalized in list_installed_versions() {
+        let Some(goroot) = get_goroot(&normalized) else {
+            continue;
+        };
+        let sdk_version = fs::read_to_string(goroot.join("VERSION"))
+            .ok()
+            .and_then(|contents| contents.lines().next().map(|line| line.trim().to_string()));
+        if let Some(sdk_version) = sdk_version.filter(|v| *v != normalized) {
+            problems.push(format!(
+                "the Go {} wrapper's SDK at {} is {}",
+                extract_version_number(&normalized),
+                goroot.display(),
+                extract_version_number(&sdk_version)
+            ));
+        }
+        roots.entry(goroot).or_default().push(normalized);
+    }
+
+    for (goroot, versions) in roots.iter().filter(|(_, versions)| versions.len() > 1) {
+        let versions: Vec<_> = versions.iter().map(|v| extract_version_number(v)).collect();
+        problems.push(format!(
+            "Go {} share the SDK at {}",
+            versions.join(", "),
+            goroot.display()
+        ));
+    }
+    problems
+}
+
+/// `gvm list --verify`: warn about each of `sdk_problems`
+fn report_sdk_problems() -> CmdResult {
+    let problems = sdk_problems();
+    if problems.is_empty() {
+        return Ok(());
+    }
+    for problem in &problems {
+        report!("{} {}", "Warning:".yellow().bold(), problem);
+    }
+    report!(
+        "Run {} for each affected version to repair it.",
+        "gvm reinstall <version>".cyan()
+    );
+    Err(Failure::Failed)
+}
+
+/// A one-line hint when go.dev has a newer stable release than `current`.
+/// Uses the cached release list, refreshing it at most once a day, and stays
+/// silent when offline or when GVM_NO_UPDATE_CHECK is set.
+fn newer_stable_notice(current: &str) -> Option<String> {
+    if std::env::var_os("GVM_NO_UPDATE_CHECK").is_some() {
+        return None;
+    }
+    let current = current.parse::<Version>().ok()?;
+
+    let interval = UPDATE_CHECK_INTERVAL.as_secs();
+    let releases = match load_release_cache() {
+        Some(cache) if now_secs().saturating_sub(cache.fetched_at) < interval => cache.releases,
+        _ => {
+            let marker = get_update_check_file()?;
+            let last_check = fs::read_to_string(&marker)
+                .ok()
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .unwrap_or(0);
+            if now_secs().saturating_sub(last_check) < interval {
+                return None;
+            }
+            if let Some(parent) = marker.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let _ = fs::write(&marker, now_secs().to_string());
+            fetch_releases().ok().map(save_release_cache)?
+        }
+    };
+
+    let newest = stable_versions(&releases).max()?;
+    if newest <= current {
+        return None;
+    }
+    let newest = newest.to_string();
+    let command = if get_go_wrapper(&normalize_version(&newest)).exists() {
+        format!("gvm use {}", newest)
+    } else {
+        format!("gvm install {}", newest)
+    };
+    Some(format!(
+        "A newer stable Go {} is available — run {}",
+        newest,
+        command.cyan()
+    ))
+}
+
+/// Whether the `go` symlink points at a wrapper that no longer exists
+fn is_go_link_dangling() -> bool {
+    let go_link = get_go_link();
+    go_link.is_symlink() && !go_link.exists()
+}
+
+fn warn_dangling_go_link() {
+    let target = fs::read_link(get_go_link())
+        .map(|t| format!("{:?}", t))
+        .unwrap_or_default();
+    report!(
+        "{} The 'go' symlink points to {}, which no longer exists.",
+        "Warning:".yellow().bold(),
+        target
+    );
+    report!(
+        "Run {} to fix it.",
+        "gvm use <version>".cyan()
+    );
+}
+
+/// Each version in the release list once, newest first, with whether it's stable
+fn available_versions(releases: &[GoRelease], stable_only: bool) -> Vec<(String, bool)> {
+    let mut seen = HashSet::new();
+    let mut versions: Vec<_> = releases
+        .iter()
+        .filter(|r| r.stable || !stable_only)
+        .map(|r| (extract_version_number(&r.version).to_string(), r.stable))
+        .filter(|(version, _)| seen.insert(version.clone()))
+        .collect();
+
+    versions.sort_by(|a, b| version_order(&b.0, &a.0));
+    versions
+}
+
+fn cmd_list_all(json: bool, args: &ListAllArgs) -> CmdResult {
+    require_network("list-all")?;
+    // Offline, an out-of-date list beats no list at all
+    let releases = match get_releases(args.refresh, json) {
+        Ok(releases) => releases,
+        Err(e) => {
+            let Some(cache) = load_release_cache() else {
+                let failure = network_failure(e);
+                report!(
+                    "Run {} once while online to cache the release list for offline use.",
+                    "gvm list-all".cyan()
+                );
+                return Err(failure);
+            };
+            report!("{} {}", "Warning:".yellow().bold(), e);
+            report!(
+                "{}",
+                format!(
+                    "(offline, showing cached data from {})",
+                    format_timestamp(cache.fetched_at)
+                )
+                .yellow()
+            );
+            cache.releases
+        }
+    };
+
+    if args.installed_only {
+        check_installed_upstream(json, &releases);
+        return Ok(());
+    }
+    if args.gaps {
+        show_version_gaps(json, &releases);
+        return Ok(());
+    }
+
+    let mut versions = available_versions(&releases, args.stable_only);
+
+    // Newest first, so the first release seen in each line is its newest
+    if args.by_minor {
+        let mut seen = HashSet::new();
+        versions.retain(|(version, _)| {
+            let line = version.parse::<Version>().ok().map(|v| (v.major, v.minor));
+            seen.insert(line)
+        });
+    }
+    if let Some(search) = &args.search {
+        let search = extract_version_number(search);
+        versions.retain(|(version, _)| version.contains(search));
+    }
+
+    let installed = list_installed_versions();
+    let installed_nums: Vec<_> = installed
+        .iter()
+        .map(|v| extract_version_number(v).to_string())
+        .collect();
+
+    let shown = if args.all {
+        versions.len()
+    } else {
+        args.limit.min(versions.len())
+    };
+
+    // go.dev lists releases newest first, so a release's position is the
+    // closest thing to its age the index offers
+    let mut release_order: Vec<&str> = Vec::new();
+    for release in &releases {
+        let version = extract_version_number(&release.version);
+        if !release_order.contains(&version) {
+            release_order.push(version);
+        }
+    }
+    let newer_releases = |version: &str| {
+        args.dates
+            .then(|| release_order.iter().position(|v| *v == version))
+            .flatten()
+    };
+
+    if json {
+        print_json(&ListAllOutput {
+            versions: versions
+                .iter()
+                .take(shown)
+                .map(|(version, stable)| ListAllEntry {
+                    version: version.clone(),
+                    stable: *stable,
+                    installed: installed_nums.contains(version),
+                    newer_releases: newer_releases(version),
+                })
+                .collect(),
+            total: versions.len(),
+        });
+        return Ok(());
+    }
+
+    let matching = args
+        .search
+        .as_ref()
+        .map(|search| format!(" matching {:?}", search))
+        .unwrap_or_default();
+    if versions.is_empty() {
+        println!("{}", format!("No versions{}.", matching).yellow());
+        return Ok(());
+    }
+
+    println!("{}", "Available Go versions:".bold());
+    println!("{}", "(stable versions marked with *, installed versions marked with ✓)".dimmed());
+    if args.dates {
+        println!(
+            "{}",
+            "(go.dev doesn't publish release dates; recency is approximate, from its list order)"
+                .dimmed()
+        );
+    }
+    println!();
+
+    for (version, stable) in versions.iter().take(shown) {
+        let is_installed = installed_nums.contains(version);
+        let stable_marker = if *stable { "*" } else { " " };
+        let install_marker = if is_installed {
+            "✓".green().to_string()
+        } else {
+            " ".to_string()
+        };
+        let recency = match newer_releases(version) {
+            Some(0) => format!("  {}", "newest".dimmed()),
+            Some(1) => format!("  {}", "~1 release ago".dimmed()),
+            Some(n) => format!("  {}", format!("~{} releases ago", n).dimmed()),
+            None => String::new(),
+        };
+
+        let version = if args.dates {
+            format!("{:<10}", version)
+        } else {
+            version.clone()
+        };
+
+        if *stable {
+            println!(
+                "  {} {} {}{}",
+                install_marker,
+                stable_marker.cyan(),
+                version.cyan(),
+                recency
+            );
+        } else {
+            println!("  {} {} {}{}", install_marker, stable_marker, version, recency);
+        }
+    }
+
+    println!();
+    let summary = if shown == versions.len() {
+        format!("Showing all {} versions{}.", versions.len(), matching)
+    } else {
+        format!("Showing latest {} of {} versions{}.", shown, versions.len(), matching)
+    };
+    println!("{}", summary.dimmed());
+    Ok(())
+}
+
+/// Compare each installed version with the release list: is it still listed,
+/// and is there a newer stable patch in its minor line?
+fn check_installed_upstream(json: bool, releases: &[GoRelease]) {
+    let entries: Vec<_> = list_installed_versions()
+        .iter()
+        .map(|installed| {
+            let version = extract_version_number(installed);
+            let listed = releases
+                .iter()
+                .any(|r| extract_version_number(&r.version) == version);
+            let newer_patch = installed.parse::<Version>().ok().and_then(|installed| {
+                stable_versions(releases)
+                    .filter(|v| v.in_minor_line(&installed) && *v > installed)
+                    .max()
+                    .map(|v| v.to_string())
+            });
+            InstalledCheckEntry {
+                version: version.to_string(),
+                listed,
+                newer_patch,
+            }
+        })
+        .collect();
+
+    if json {
+        print_json(&entries);
+        return;
+    }
+
+    if entries.is_empty() {
+        println!("{}", "No Go versions installed.".yellow());
+        return;
+    }
+
+    println!("{}", "Installed Go versions compared with go.dev:".bold());
+    for entry in &entries {
+        let status = match (&entry.newer_patch, entry.listed) {
+            (Some(newer), _) => format!("newer patch available: {}", newer).yellow(),
+            (None, false) => "no longer listed upstream".red(),
+            (None, true) => "up to date".green(),
+        };
+        println!("  {:<12} {}", entry.version, status);
+    }
+}
+
+/// Show, per installed minor line, which stable patches are installed and
+/// which newer ones are missing, newest first like a diff against go.dev
+fn show_version_gaps(json: bool, releases: &[GoRelease]) {
+    let stable: BTreeSet<Version> = stable_versions(releases).collect();
+    let mut lines: BTreeMap<(u32, u32), BTreeSet<Version>> = BTreeMap::new();
+    for installed in list_installed_versions() {
+        if let Ok(version) = installed.parse::<Version>() {
+            lines
+                .entry((version.major, version.minor))
+                .or_default()
+                .insert(version);
+        }
+    }
+
+    let gaps: Vec<_> = lines
+        .iter()
+        .rev()
+        .map(|(&(major, minor), installed)| {
+            let line = Version {
+                major,
+                minor,
+                patch: None,
+                pre: PreRelease::Final,
+            };
+            let upstream: Vec<_> = stable.iter().filter(|v| v.in_minor_line(&line)).collect();
+            let newest_installed = installed.iter().next_back();
+            MinorLineGaps {
+                line: line.to_string(),
+                installed: installed
+                    .iter()
+                    .rev()
+                    .map(|version| InstalledPatch {
+                        version: version.to_string(),
+                        newer_available: upstream.iter().filter(|v| **v > version).count(),
+                    })
+                    .collect(),
+                missing: upstream
+                    .iter()
+                    .rev()
+                    .filter(|v| newest_installed.is_some_and(|newest| **v > newest))
+                    .map(|v| v.to_string())
+                    .collect(),
+            }
+        })
+        .collect();
+
+    if json {
+        print_json(&gaps);
+        return;
+    }
+
+    if gaps.is_empty() {
+        println!("{}", "No Go versions installed.".yellow());
+        return;
+    }
+
+    println!("{}", "Installed minor lines compared with go.dev:".bold());
+    println!("{}", "(+ newer patch not installed, ✓ installed)".dimmed());
+    for line in &gaps {
+        println!();
+        println!("  {}", line.line.bold());
+        for missing in &line.missing {
+            println!("    {} {}", "+".yellow(), missing.yellow());
+        }
+        for patch in &line.installed {
+            let status = match patch.newer_available {
+                0 => "(up to date)".to_string().dimmed(),
+                n => format!("({} newer available)", n).yellow(),
+            };
+            println!("    {} {} {}", "✓".green(), patch.version.green(), status);
+        }
+    }
+}
+
+/// Install several versions, carrying on past failures. Wrappers are
+/// installed one at a time so concurrent `go install` runs don't race on the
+/// module cache; the SDK downloads, which are network-bound, run in parallel.
+fn cmd_install_many(versions: &[String], options: &InstallArgs) -> CmdResult {
+    require_network("install")?;
+    if let [version] = versions {
+        return cmd_install(version, options);
+    }
+
+    let mut results = Vec::new();
+    let mut pending = Vec::new();
+    for (i, version) in versions.iter().enumerate() {
+        if i > 0 {
+            info!();
+        }
+        match start_install(version, options) {
+            Ok(Some(download)) => {
+                pending.push((i, download));
+                results.push(Ok(()));
+            }
+            result => results.push(result.map(|_| ())),
+        }
+    }
+
+    if !pending.is_empty() {
+        info!();
+        info!(
+            "{}",
+            format!("Downloading {} Go SDKs...", pending.len()).dimmed()
+        );
+        let downloads: Vec<_> = pending.iter().map(|(_, download)| download).collect();
+        let downloaded = download_sdks(&downloads);
+        for ((i, download), result) in pending.iter().zip(downloaded) {
+            info!();
+            results[*i] = result.and_then(|_| finish_install(download, options.quiet));
+        }
+    }
+
+    if options.dry_run {
+        return Ok(());
+    }
+
+    info!();
+    info!("{}", "Install summary:".bold());
+    for (version, result) in versions.iter().zip(&results) {
+        let marker = if result.is_ok() {
+            "✓".green().bold()
+        } else {
+            "✗".red().bold()
+        };
+        info!("  {} {}", marker, extract_version_number(version));
+    }
+
+    if results.iter().all(Result::is_ok) {
+        Ok(())
+    } else {
+        Err(Failure::Failed)
+    }
+}
+
+/// Wipe whatever is left of a version, then run the full install flow
+fn cmd_reinstall(version: &str, quiet: bool) -> CmdResult {
+    require_network("reinstall")?;
+    let version = resolve_alias(version)?;
+    let normalized = normalize_version(&version);
+
+    let options = InstallArgs {
+        quiet,
+        ..InstallArgs::default()
+    }
+    .with_defaults();
+
+    // A --dry-run among the default install flags must not delete anything
+    if options.dry_run {
+        println!(
+            "{} {}",
+            "Would reinstall Go version:".bold(),
+            extract_version_number(&normalized).green()
+        );
+        println!("  Remove {:?}", get_go_wrapper(&normalized));
+        println!("  Remove {:?}", get_sdk_dir().join(&normalized));
+        println!("  Then install it again as `gvm install` would");
+        return Ok(());
+    }
+
+    info!(
+        "{} {}",
+        "Reinstalling Go version:".bold(),
+        extract_version_number(&normalized).green()
+    );
+    if !remove_version(&normalized) {
+        return Err(Failure::Failed);
+    }
+    cmd_install(&normalized, &options)
+}
+
+/// Run an install step. Its output streams straight to the terminal so
+/// download progress is visible; when `quiet`, output is captured instead
+/// and only shown if the step fails. The child is killed with a `TimedOut`
+/// error if it's still running after `timeout`.
+fn run_child_with_timeout(
+    command: &mut Command,
+    quiet: bool,
+    timeout: Option<Duration>,
+) -> io::Result<ExitStatus> {
+    let (status, stderr) = spawn_child(command, quiet, quiet, timeout)?;
+    if !status.success() {
+        io::stderr().write_all(&stderr)?;
+    }
+    Ok(status)
+}
+
+/// `run_child_with_timeout`, but stderr is always captured and handed back rather than
+/// printed, so a failure can be reported along with the child's own reason
+fn run_child_captured(command: &mut Command, quiet: bool) -> io::Result<(ExitStatus, String)> {
+    let (status, stderr) = spawn_child(command, quiet, true, None)?;
+    Ok((status, String::from_utf8_lossy(&stderr).into_owned()))
+}
+
+fn spawn_child(
+    command: &mut Command,
+    quiet: bool,
+    capture_stderr: bool,
+    timeout: Option<Duration>,
+) -> io::Result<(ExitStatus, Vec<u8>)> {
+    log_command(command);
+    command.stdout(if quiet { Stdio::null() } else { Stdio::inherit() });
+    command.stderr(if capture_stderr {
+        Stdio::piped()
+    } else {
+        Stdio::inherit()
+    });
+
+    let mut child = command.spawn()?;
+    // Drain captured stderr on its own thread so the child can't stall on a
+    // full pipe while we wait for it
+    let stderr = child.stderr.take().map(|mut pipe| {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = pipe.read_to_end(&mut buf);
+            buf
+        })
+    });
+
+    let status = match timeout {
+        Some(timeout) => wait_with_timeout(&mut child, timeout)?,
+        None => child.wait()?,
+    };
+    let stderr = stderr.and_then(|h| h.join().ok()).unwrap_or_default();
+    Ok((status, stderr))
+}
+
+fn wait_with_timeout(child: &mut Child, timeout: Duration) -> io::Result<ExitStatus> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("timed out after {}s", timeout.as_secs()),
+            ));
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+fn cmd_install(version: &str, options: &InstallArgs) -> CmdResult {
+    require_network("install")?;
+    let Some(download) = start_install(version, options)? else {
+        return Ok(());
+    };
+
+    info!("{}", "Step 2/3: Downloading Go SDK...".dimmed());
+    download_sdk(&download, options.quiet)?;
+    info!("{}", "  ✓ Go SDK downloaded".green());
+
+    finish_install(&download, options.quiet)
+}
+
+fn print_sdk_download_hint(version_num: &str) {
+    info!(
+        "Run {} to download its SDK.",
+        format!("gvm install {}", version_num).cyan()
+    );
+}
+
+/// Resolve a version and install its `goX.X.X` wrapper, returning the SDK
+/// download still to be done. Installs that need no wrapper (already
+/// installed, another platform, or no bootstrap `go`) are finished here and
+/// return `None`.
+fn start_install(
+    version: &str,
+    options: &InstallArgs,
+) -> Result<Option<PendingDownload>, Failure> {
+    let quiet = options.quiet;
+    let os = options.os.as_deref().unwrap_or(host_go_os());
+    let arch = options.arch.as_deref().unwrap_or(host_archive_arch());
+    let is_present = |normalized: &str| {
+        if os == host_go_os() && arch == host_archive_arch() {
+            is_fully_installed(normalized) && !is_partial_sdk(normalized)
+        } else {
+            get_sdk_root(normalized, os, arch)
+                .join(".unpacked-success")
+                .exists()
+        }
+    };
+
+    // An exact version that's already there needs no release list lookup,
+    // so re-runs work offline
+    if options.skip_if_present && is_present(&normalize_version(version)) {
+        return Ok(None);
+    }
+
+    let version = resolve_install_version(version)?;
+    let normalized = normalize_version(&version);
+    let version_num = extract_version_number(&normalized);
+    validate_version(version_num)?;
+    if options.skip_if_present && is_present(&normalized) {
+        return Ok(None);
+    }
+    if options.dry_run {
+        print_install_plan(&normalized, os, arch, options);
+        return Ok(None);
+    }
+
+    // SDKs for other platforms can only come from the official archives
+    if os != host_go_os() || arch != host_archive_arch() {
+        let sdk_root = get_sdk_root(&normalized, os, arch);
+        if sdk_root.join(".unpacked-success").exists() {
+            info!(
+                "{} Go {} for {}/{} is already downloaded to {:?}",
+                "✓".green().bold(),
+                version_num.green(),
+                os,
+                arch,
+                sdk_root
+            );
+            return Ok(None);
+        }
+
+        info!(
+            "{} {} {}",
+            "Installing Go version:".bold(),
+            version_num.green(),
+            format!("({}/{})", os, arch).dimmed()
+        );
+        return install_from_archive(&normalized, os, arch).map(|_| None);
+    }
+
+    // Check if already installed
+    let go_wrapper = get_go_wrapper(&normalized);
+    let partial = is_partial_sdk(&normalized);
+
+    if is_fully_installed(&normalized) && !partial {
+        info!(
+            "{} Go {} is already installed.",
+            "✓".green().bold(),
+            version_num.green()
+        );
+        info!(
+            "Use {} to switch to this version.",
+            format!("gvm use {}", version_num).cyan()
+        );
+        return Ok(None);
+    }
+
+    // Start an interrupted download over rather than trusting what it left
+    if partial {
+        let sdk_root = get_sdk_root(&normalized, os, arch);
+        info!(
+            "{}",
+            format!(
+                "Removing the incomplete Go {} SDK left by an interrupted download...",
+                version_num
+            )
+            .yellow()
+        );
+        if let Err(e) = fs::remove_dir_all(&sdk_root) {
+            report!(
+                "{} Failed to remove {:?}: {}",
+                "Error:".red().bold(),
+                sdk_root,
+                e
+            );
+            return Err(Failure::Failed);
+        }
+    }
+
+    // A wrapper staged by --only-wrapper only needs its SDK
+    if go_wrapper.exists() {
+        if options.only_wrapper {
+            info!(
+                "{} The Go {} wrapper is already installed.",
+                "✓".green().bold(),
+                version_num.green()
+            );
+            print_sdk_download_hint(version_num);
+            return Ok(None);
+        }
+        info!(
+            "{} {} {}",
+            "Installing Go version:".bold(),
+            version_num.green(),
+            "(wrapper already installed)".dimmed()
+        );
+        return Ok(Some(PendingDownload {
+            normalized,
+            go_wrapper,
+            timeout: options.timeout.map(Duration::from_secs),
+        }));
+    }
+
+    info!(
+        "{} {}",
+        "Installing Go version:".bold(),
+        version_num.green()
+    );
+
+    // Without a bootstrap `go` there's nothing to run `go install` with
+    let Some(go_on_path) = find_on_path("go") else {
+        if options.only_wrapper {
+            report!(
+                "{} Installing only the wrapper needs 'go' on PATH to run `go install`.",
+                "Error:".red().bold()
+            );
+            return Err(Failure::Failed);
+        }
+        info!(
+            "{}",
+            "No 'go' found on PATH; installing from the official archive.".dimmed()
+        );
+        return install_from_archive(&normalized, os, arch).map(|_| None);
+    };
+    let bootstrap = bootstrap_go(go_on_path);
+
+    // A bootstrap built for another platform (an amd64 Go under Rosetta on
+    // an arm64 Mac, say) can't build a native wrapper with GOBIN set, and its
+    // own wrapper would fetch the wrong SDK
+    let host_platform = format!("{}/{}", host_go_os(), host_goarch_env());
+    if let Some(platform) = go_host_platform(&bootstrap).filter(|p| *p != host_platform) {
+        report!(
+            "{} {} is a {} Go, but this machine is {}.",
+            "Warning:".yellow().bold(),
+            bootstrap.display(),
+            platform,
+            host_platform
+        );
+        if options.only_wrapper {
+            report!(
+                "{} Installing only the wrapper needs a {} 'go' to run `go install`.",
+                "Error:".red().bold(),
+                host_platform
+            );
+            return Err(Failure::Failed);
+        }
+        info!(
+            "{}",
+            "Installing from the official archive instead.".dimmed()
+        );
+        return install_from_archive(&normalized, os, arch).map(|_| None);
+    }
+
+    // Step 1: go install golang.org/dl/goX.X.X@latest
+    info!("{}", "Step 1/3: Installing Go wrapper...".dimmed());
+    let install_result =
+        run_child_captured(&mut sdk::wrapper_install_command(&bootstrap, &normalized), quiet);
+
+    match install_result {
+        Ok((status, stderr)) if status.success() => {
+            // Keep go's own "downloading ..." progress visible as before
+            if !quiet {
+                eprint!("{}", stderr);
+            }
+            info!("{}", "  ✓ Go wrapper installed".green());
+        }
+        Ok((status, stderr)) => {
+            report!(
+                "{} go install failed with exit code: {:?}",
+                "Error:".red().bold(),
+                status.code()
+            );
+            // go's stderr carries the actual reason (unknown version,
+            // network or proxy errors), so pass it on
+            for line in stderr.lines().filter(|line| !line.trim().is_empty()) {
+                report!("  {}", line);
+            }
+            return Err(Failure::Failed);
+        }
+        Err(e) => {
+            report!("{} Failed to run go install: {}", "Error:".red().bold(), e);
+            report!(
+                "{}",
+                "Make sure 'go' is installed and available in your PATH.".yellow()
+            );
+            return Err(Failure::Failed);
+        }
+    }
+
+    if !go_wrapper.exists() {
+        report!(
+            "{} Go wrapper not found at {:?}",
+            "Error:".red().bold(),
+            go_wrapper
+        );
+        return Err(Failure::Failed);
+    }
+
+    if options.only_wrapper {
+        info!();
+        info!(
+            "{} Go {} wrapper installed without its SDK.",
+            "✓".green().bold(),
+            version_num.green()
+        );
+        print_sdk_download_hint(version_num);
+        return Ok(None);
+    }
+
+    Ok(Some(PendingDownload {
+        normalized,
+        go_wrapper,
+        timeout: options.timeout.map(Duration::from_secs),
+    }))
+}
+
+/// Step 2: run `goX.X.X download` to fetch the SDK
+fn download_sdk(download: &PendingDownload, quiet: bool) -> CmdResult {
+    let version_num = extract_version_number(&download.normalized);
+
+    let mut command = Command::new(&download.go_wrapper);
+    command.arg("download");
+    match run_child_with_timeout(&mut command, quiet, download.timeout) {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => {
+            report!(
+                "{} Go {} download failed with exit code: {:?}",
+                "Error:".red().bold(),
+                version_num,
+                status.code()
+            );
+            Err(Failure::Network)
+        }
+        Err(e) => {
+            report!(
+                "{} Failed to download Go {} SDK: {}",
+                "Error:".red().bold(),
+                version_num,
+                e
+            );
+            // Don't leave a half-downloaded SDK for the next attempt to trip over
+            if e.kind() == io::ErrorKind::TimedOut {
+                let _ = fs::remove_dir_all(get_sdk_root(
+                    &download.normalized,
+                    host_go_os(),
+                    host_archive_arch(),
+                ));
+            }
+            Err(Failure::Network)
+        }
+    }
+}
+
+/// Most SDKs downloaded at once when installing several versions
+const MAX_PARALLEL_DOWNLOADS: usize = 4;
+
+/// Download several SDKs concurrently, printing a line as each one starts
+/// and finishes. Their own output is captured and only shown on failure.
+fn download_sdks(downloads: &[&PendingDownload]) -> Vec<CmdResult> {
+    run_parallel(downloads, MAX_PARALLEL_DOWNLOADS, |download| {
+        let version_num = extract_version_number(&download.normalized);
+        info!("  {} Go {}", "↓".cyan(), version_num);
+
+        let result = download_sdk(download, true);
+        if result.is_ok() {
+            info!("{}", format!("  ✓ Go {} SDK downloaded", version_num).green());
+        }
+        result
+    })
+}
+
+/// Run `work` on each item with at most `limit` running at once, returning
+/// the results in the order of `items`
+fn run_parallel<T: Sync, R: Send>(
+    items: &[T],
+    limit: usize,
+    work: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let queue = Mutex::new(items.iter().enumerate());
+    let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<_>>());
+
+    thread::scope(|scope| {
+        for _ in 0..limit.min(items.len()) {
+            scope.spawn(|| loop {
+                let Some((i, item)) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                let result = work(item);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+
+    // A worker that panicked would have re-raised it when the scope ended,
+    // so every item has its result here
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every item was run"))
+        .collect()
+}
+
+/// Print the steps `start_install` would take for a resolved version,
+/// following the same decisions without running or writing anything
+fn print_install_plan(normalized: &str, os: &str, arch: &str, options: &InstallArgs) {
+    let version_num = extract_version_number(normalized);
+    println!(
+        "{} {} {}",
+        "Would install Go version:".bold(),
+        version_num.green(),
+        format!("({}/{})", os, arch).dimmed()
+    );
+    println!("  Release list and archives from {}", download_base());
+    let proxy = network_options()
+        .proxy
+        .clone()
+        .or_else(|| std::env::var("HTTPS_PROXY").ok())
+        .or_else(|| std::env::var("https_proxy").ok());
+    if let Some(proxy) = proxy {
+        println!("  Through proxy {}", proxy);
+    }
+
+    if os != host_go_os() || arch != host_archive_arch() {
+        let sdk_root = get_sdk_root(normalized, os, arch);
+        if sdk_root.join(".unpacked-success").exists() {
+            println!("  Already downloaded to {}; nothing to do.", sdk_root.display());
+        } else {
+            print_archive_plan(normalized, os, arch);
+        }
+        return;
+    }
+
+    let go_wrapper = get_go_wrapper(normalized);
+    let partial = is_partial_sdk(normalized);
+    if is_fully_installed(normalized) && !partial {
+        println!("  Already installed; nothing to do.");
+        return;
+    }
+    if partial {
+        println!(
+            "  Remove the incomplete SDK at {} first",
+            get_sdk_root(normalized, os, arch).display()
+        );
+    }
+
+    if go_wrapper.exists() {
+        println!("  1. Keep the installed wrapper {}", go_wrapper.display());
+    } else {
+        match find_on_path("go") {
+            Some(go_on_path) => {
+                println!(
+                    "  1. Run GOBIN={} GOOS={} GOARCH={} {} install golang.org/dl/{}@latest",
+                    get_go_bin_dir().display(),
+                    host_go_os(),
+                    host_goarch_env(),
+                    bootstrap_go(go_on_path).display(),
+                    normalized
+                );
+                println!("     creating the wrapper {}", go_wrapper.display());
+            }
+            None if options.only_wrapper => {
+                println!("  No 'go' on PATH to run `go install` with, so the wrapper can't be installed.");
+                return;
+            }
+            None => {
+                println!("  No 'go' on PATH, so the official archive would be used:");
+                print_archive_plan(normalized, os, arch);
+                return;
+            }
+        }
+    }
+
+    if options.only_wrapper {
+        println!("  2. Skip the SDK download (--only-wrapper)");
+        return;
+    }
+    println!("  2. Run {} download", go_wrapper.display());
+    println!(
+        "     unpacking the SDK to {}",
+        get_sdk_root(normalized, os, arch).display()
+    );
+    println!("  3. Verify the SDK against the checksum listed in {}", checksum_source());
+}
+
+fn print_archive_plan(normalized: &str, os: &str, arch: &str) {
+    println!(
+        "  1. Download {}{}.{}-{}.tar.gz",
+        download_base(),
+        normalized,
+        os,
+        arch
+    );
+    println!(
+        "  2. Unpack it to {}",
+        get_sdk_root(normalized, os, arch).display()
+    );
+    println!("  3. Verify it against the checksum listed in {}", checksum_source());
+}
+
+/// Step 3: check the SDK archive against the checksum published on go.dev
+fn finish_install(download: &PendingDownload, quiet: bool) -> CmdResult {
+    let version_num = extract_version_number(&download.normalized);
+
+    info!(
+        "{}",
+        format!("Step 3/3: Verifying checksum for Go {}...", version_num).dimmed()
+    );
+    verify_sdk_with_retry(version_num, host_go_os(), host_archive_arch(), || {
+        download_sdk(download, quiet)
+    })?;
+
+    print_install_success(version_num);
+    Ok(())
+}
+
+fn print_install_success(version_num: &str) {
+    info!();
+    info!(
+        "{} Go {} installed successfully!",
+        "✓".green().bold(),
+        version_num.green()
+    );
+    info!(
+        "Use {} to switch to this version.",
+        format!("gvm use {}", version_num).cyan()
+    );
+}
+
+/// Times an SDK is downloaded before a checksum mismatch is reported as an error
+const CHECKSUM_ATTEMPTS: u32 = 2;
+
+/// Check the downloaded SDK's checksum. A mismatch deletes the SDK and runs
+/// `download` again, failing only once CHECKSUM_ATTEMPTS downloads were bad.
+fn verify_sdk_with_retry(
+    version_num: &str,
+    os: &str,
+    arch: &str,
+    download: impl Fn() -> CmdResult,
+) -> CmdResult {
+    let mut attempt = 1;
+    loop {
+        let last = attempt == CHECKSUM_ATTEMPTS;
+        match check_sdk_checksum(version_num, os, arch, last) {
+            Err(failure) if last => return Err(failure),
+            Err(_) => attempt += 1,
+            Ok(()) => return Ok(()),
+        }
+
+        info!(
+            "{}",
+            format!(
+                "Downloading Go {} again (attempt {} of {})...",
+                version_num,
+                attempt,
+                CHECKSUM_ATTEMPTS
+            )
+            .yellow()
+        );
+        download()?;
+    }
+}
+
+/// Check the downloaded SDK's checksum, deleting the SDK if it doesn't match.
+/// The mismatch is only reported as an error on the `last` attempt.
+fn check_sdk_checksum(version_num: &str, os: &str, arch: &str, last: bool) -> CmdResult {
+    match verify_sdk_checksum(version_num, os, arch) {
+        Verification::Verified => {
+            info!("{}", "  ✓ Checksum verified".green());
+            Ok(())
+        }
+        Verification::Skipped(reason) => {
+            info!(
+                "  {} Skipping checksum verification: {}",
+                "!".yellow().bold(),
+                reason
+            );
+            Ok(())
+        }
+        Verification::Mismatch { expected, actual } => {
+            let label = if last {
+                "Error:".red().bold()
+            } else {
+                "Warning:".yellow().bold()
+            };
+            report!("{} Checksum mismatch for Go {}", label, version_num);
+            report!("  expected: {}", expected);
+            report!("  actual:   {}", actual);
+
+            let sdk_dir = get_sdk_root(&normalize_version(version_num), os, arch);
+            match fs::remove_dir_all(&sdk_dir) {
+                Ok(_) => report!("{}", "Removed the corrupted SDK.".yellow()),
+                Err(e) => report!(
+                    "{} Failed to remove corrupted SDK {:?}: {}",
+                    "Error:".red().bold(),
+                    sdk_dir,
+                    e
+                ),
+            }
+            Err(Failure::Failed)
+        }
+    }
+}
+
+fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Verify the archive `goX.X.X download` left in the SDK directory against
+/// the SHA256 go.dev publishes for the given OS/arch
+fn verify_sdk_checksum(version_num: &str, os: &str, arch: &str) -> Verification {
+    match get_releases(false, true) {
+        Ok(releases) => verify_sdk_archive(&releases, version_num, os, arch),
+        Err(e) => Verification::Skipped(e),
+    }
+}
+
+/// `verify_sdk_checksum` against an already fetched release list
+fn verify_sdk_archive(
+    releases: &[GoRelease],
+    version_num: &str,
+    os: &str,
+    arch: &str,
+) -> Verification {
+    let normalized = normalize_version(version_num);
+    let Some(file) = releases
+        .iter()
+        .filter(|r| r.version == normalized)
+        .flat_map(|r| &r.files)
+        .find(|f| f.kind == "archive" && f.os == os && f.arch == arch)
+    else {
+        return Verification::Skipped(format!("no published checksum for {}/{}", os, arch));
+    };
+
+    let archive = get_sdk_root(&normalized, os, arch).join(&file.filename);
+    match sha256_file(&archive) {
+        Ok(actual) if actual == file.sha256 => Verification::Verified,
+        Ok(actual) => Verification::Mismatch {
+            expected: file.sha256.clone(),
+            actual,
+        },
+        Err(e) => Verification::Skipped(format!("could not read {:?}: {}", archive, e)),
+    }
+}
+
+/// Most SDK archives hashed at once by `gvm verify`
+const MAX_PARALLEL_VERIFICATIONS: usize = 4;
+
+/// Verify the given installed versions, or all of them when none are given.
+/// Archives are hashed a few at a time and reported in version order.
+fn cmd_verify(requested: &[String]) -> CmdResult {
+    let mut versions = Vec::new();
+    for version in requested {
+        let normalized = normalize_version(&resolve_alias(version)?);
+        if !get_go_wrapper(&normalized).exists() {
+            report!(
+                "{} Go {} is not installed.",
+                "Error:".red().bold(),
+                extract_version_number(&normalized)
+            );
+            return Err(Failure::NotInstalled);
+        }
+        versions.push(normalized);
+    }
+    if requested.is_empty() {
+        versions = list_installed_versions();
+    }
+    if versions.is_empty() {
+        println!("No Go versions installed.");
+        return Ok(());
+    }
+
+    let releases = get_releases(false, true).map_err(network_failure)?;
+    info!(
+        "{}",
+        format!("Verifying {} Go SDKs...", versions.len()).dimmed()
+    );
+
+    let results = run_parallel(&versions, MAX_PARALLEL_VERIFICATIONS, |normalized| {
+        if is_fully_installed(normalized) {
+            verify_sdk_archive(
+                &releases,
+                extract_version_number(normalized),
+                host_go_os(),
+                host_archive_arch(),
+            )
+        } else {
+            Verification::Skipped("its SDK is not downloaded".to_string())
+        }
+    });
+
+    let mut verified = 0;
+    let mut mismatched = Vec::new();
+    for (normalized, verification) in versions.iter().zip(results) {
+        let version_num = extract_version_number(normalized);
+        match verification {
+            Verification::Verified => {
+                verified += 1;
+                println!("  {} {}", "✓".green().bold(), version_num);
+            }
+            Verification::Mismatch { expected, actual } => {
+                println!(
+                    "  {} {}: checksum mismatch (expected {}, got {})",
+                    "✗".red().bold(),
+                    version_num,
+                    expected,
+                    actual
+                );
+                mismatched.push(version_num);
+            }
+            Verification::Skipped(reason) => {
+                println!("  {} {}: skipped, {}", "!".yellow().bold(), version_num, reason);
+            }
+        }
+    }
+
+    println!();
+    println!("Verified {} of {} Go SDKs.", verified, versions.len());
+    if mismatched.is_empty() {
+        return Ok(());
+    }
+    report!(
+        "{} {} SDKs don't match their published checksums.",
+        "Error:".red().bold(),
+        mismatched.len()
+    );
+    for version_num in mismatched {
+        report!(
+            "Run {} to repair it.",
+            format!("gvm reinstall {}", version_num).cyan()
+        );
+    }
+    Err(Failure::Failed)
+}
+
+/// Whether `dir` is one of the entries of `$PATH`
+fn is_on_path(dir: &Path) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    let canonical = fs::canonicalize(dir).ok();
+    std::env::split_paths(&path).any(|entry| {
+        entry == dir || (canonical.is_some() && fs::canonicalize(&entry).ok() == canonical)
+    })
+}
+
+/// Name of the user's shell, from `$SHELL`
+fn detect_shell() -> String {
+    std::env::var("SHELL")
+        .ok()
+        .and_then(|shell| {
+            Path::new(&shell)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .unwrap_or_else(|| "sh".to_string())
+}
+
+/// Whether a version is usable: its wrapper exists and reports an SDK root
+/// containing `bin/go`. A wrapper whose `download` failed or was deferred
+/// with `--only-wrapper` doesn't count.
+fn is_fully_installed(normalized: &str) -> bool {
+    get_go_wrapper(normalized).exists()
+        && get_goroot(normalized)
+            .is_some_and(|goroot| goroot.join("bin").join(format!("go{}", EXE_SUFFIX)).is_file())
+}
+
+/// Explain why a version with a wrapper but no SDK can't be used
+fn print_missing_sdk(version_num: &str) {
+    report!(
+        "{} Go {} is not fully installed: its SDK is missing.",
+        "Error:".red().bold(),
+        version_num
+    );
+    report!(
+        "Run {} to download it.",
+        format!("gvm install {}", version_num).cyan()
+    );
+}
+
+/// SDK root of an installed version, as reported by its wrapper's
+/// `go env GOROOT`
+fn get_goroot(normalized: &str) -> Option<PathBuf> {
+    let mut command = Command::new(get_go_wrapper(normalized));
+    command.args(["env", "GOROOT"]);
+    log_command(&command);
+    let output = command.output().ok()?;
+    let goroot = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !goroot.is_empty()).then(|| PathBuf::from(goroot))
+}
+
+/// The `go` to run `go install golang.org/dl/...` with. When the `go` on PATH
+/// is gvm's own link it may point at an old release, so the newest installed
+/// version is used instead, then the first `go` outside gvm.
+fn bootstrap_go(go_on_path: PathBuf) -> PathBuf {
+    if go_on_path != get_go_link() {
+        log_verbose(&format!("bootstrap go: {}", go_on_path.display()));
+        return go_on_path;
+    }
+
+    let mut installed = list_installed_versions();
+    installed.sort_by(|a, b| version_order(b, a));
+    let newest = installed
+        .iter()
+        .find(|v| is_fully_installed(v))
+        .map(|v| get_go_wrapper(v));
+    let go = newest.or_else(find_system_go).unwrap_or(go_on_path);
+    info!(
+        "{}",
+        format!("Using {} to install the wrapper.", go.display()).dimmed()
+    );
+    go
+}
+
+/// Find an executable on `$PATH`
+fn find_on_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    let file_name = format!("{}{}", name, EXE_SUFFIX);
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Unpack a `go<version>.<os>-<arch>.tar.gz` into `sdk_dir`, dropping the
+/// archive's top-level `go/` directory so the layout matches `goX.X.X download`
+fn extract_sdk_archive(archive: &Path, sdk_dir: &Path) -> io::Result<()> {
+    let file = fs::File::open(archive)?;
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let Ok(relative) = path.strip_prefix("go") else {
+            continue;
+        };
+
+        // Refuse entries that would escape the SDK directory
+        if relative
+            .components()
+            .any(|c| !matches!(c, std::path::Component::Normal(_)))
+        {
+            continue;
+        }
+
+        let target = sdk_dir.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&target)?;
+    }
+
+    Ok(())
+}
+
+/// Install a version from the official tarball on go.dev. For the host
+/// platform the `goX.X.X` wrapper is pointed at its `go` binary; SDKs for
+/// other platforms are only downloaded, since they can't run here.
+fn install_from_archive(normalized: &str, os: &str, arch: &str) -> CmdResult {
+    let version_num = extract_version_number(normalized);
+
+    // Step 1: download go<version>.<os>-<arch>.tar.gz
+    info!("{}", "Step 1/3: Downloading Go SDK archive...".dimmed());
+
+    let releases = get_releases(false, true).map_err(network_failure)?;
+    let Some(file) = releases
+        .iter()
+        .filter(|r| r.version == normalized)
+        .flat_map(|r| &r.files)
+        .find(|f| {
+            f.kind == "archive" && f.os == os && f.arch == arch && f.filename.ends_with(".tar.gz")
+        })
+    else {
+        report!(
+            "{} No .tar.gz archive of Go {} found for {}/{}",
+            "Error:".red().bold(),
+            version_num,
+            os,
+            arch
+        );
+        return Err(Failure::Failed);
+    };
+
+    let sdk_dir = get_sdk_root(normalized, os, arch);
+    let archive = sdk_dir.join(&file.filename);
+    let url = format!("{}{}", download_base(), file.filename);
+    let download = || {
+        if let Err(e) = fs::create_dir_all(&sdk_dir) {
+            report!(
+                "{} Failed to create {:?}: {}",
+                "Error:".red().bold(),
+                sdk_dir,
+                e
+            );
+            return Err(Failure::Failed);
+        }
+        net::download_file(&url, &archive).map_err(network_failure)?;
+        info!("{}", "  ✓ Go SDK archive downloaded".green());
+        Ok(())
+    };
+    download()?;
+
+    // Step 2: check it against the published checksum
+    info!("{}", "Step 2/3: Verifying checksum...".dimmed());
+    verify_sdk_with_retry(version_num, os, arch, download)?;
+
+    // Step 3: unpack it and set up the wrapper
+    info!("{}", "Step 3/3: Extracting Go SDK...".dimmed());
+    unpack_sdk(&archive, normalized, os, arch)
+}
+
+/// Extract an SDK archive into the version's SDK directory and, for this
+/// machine's platform, link the `goX.X.X` wrapper to it
+fn unpack_sdk(archive: &Path, normalized: &str, os: &str, arch: &str) -> CmdResult {
+    let version_num = extract_version_number(normalized);
+    let sdk_dir = get_sdk_root(normalized, os, arch);
+    if let Err(e) = fs::create_dir_all(&sdk_dir) {
+        report!(
+            "{} Failed to create {:?}: {}",
+            "Error:".red().bold(),
+            sdk_dir,
+            e
+        );
+        return Err(Failure::Failed);
+    }
+
+    if let Err(e) = extract_sdk_archive(archive, &sdk_dir) {
+        report!(
+            "{} Failed to extract {:?}: {}",
+            "Error:".red().bold(),
+            archive,
+            e
+        );
+        return Err(Failure::Failed);
+    }
+    // Same marker `goX.X.X download` leaves, so the official wrapper accepts this SDK
+    let _ = fs::write(sdk_dir.join(".unpacked-success"), "");
+
+    if os != host_go_os() || arch != host_archive_arch() {
+        info!("{}", "  ✓ Go SDK extracted".green());
+        info!();
+        info!(
+            "{} Go {} for {}/{} installed to {:?}",
+            "✓".green().bold(),
+            version_num.green(),
+            os,
+            arch,
+            sdk_dir
+        );
+        return Ok(());
+    }
+
+    let go_wrapper = get_go_wrapper(normalized);
+    let sdk_go = sdk_dir.join("bin").join(format!("go{}", EXE_SUFFIX));
+    // Replaces a wrapper left dangling by an earlier install of this version
+    if let Err(e) = switch_go_link(&sdk_go, &go_wrapper) {
+        report!(
+            "{} Failed to create wrapper {:?}: {}",
+            "Error:".red().bold(),
+            go_wrapper,
+            e
+        );
+        return Err(Failure::Failed);
+    }
+    info!("{}", "  ✓ Go SDK extracted".green());
+
+    print_install_success(version_num);
+    Ok(())
+}
+
+/// Read the version, GOOS and GOARCH from an official archive name like
+/// `go1.22.5.linux-amd64.tar.gz`
+fn parse_archive_name(name: &str) -> Option<(String, String, String)> {
+    let stem = name.strip_suffix(".tar.gz")?;
+    let (version, platform) = stem.rsplit_once('.')?;
+    let (os, arch) = platform.split_once('-')?;
+    version.parse::<Version>().ok()?;
+    Some((
+        extract_version_number(version).to_string(),
+        os.to_string(),
+        arch.to_string(),
+    ))
+}
+
+/// Install an SDK archive that was downloaded separately, without any
+/// network access
+fn cmd_install_from_file(
+    archive: &Path,
+    version: Option<&str>,
+    options: &InstallArgs,
+) -> CmdResult {
+    if !archive.is_file() {
+        report!("{} {:?} is not a file.", "Error:".red().bold(), archive);
+        return Err(Failure::Failed);
+    }
+
+    let inferred = archive
+        .file_name()
+        .and_then(|name| parse_archive_name(&name.to_string_lossy()));
+    let Some(version) = version
+        .map(|v| extract_version_number(v).to_string())
+        .or_else(|| inferred.as_ref().map(|(v, _, _)| v.clone()))
+    else {
+        report!(
+            "{} Couldn't tell the Go version from {:?}.",
+            "Error:".red().bold(),
+            archive
+        );
+        report!("Pass it with {}, e.g. --version 1.22.5.", "--version".cyan());
+        return Err(Failure::Failed);
+    };
+    if let Err(e) = version.parse::<Version>() {
+        report!("{} {}", "Error:".red().bold(), e);
+        return Err(Failure::Failed);
+    }
+
+    let os = options
+        .os
+        .clone()
+        .or_else(|| inferred.as_ref().map(|(_, os, _)| os.clone()))
+        .unwrap_or_else(|| host_go_os().to_string());
+    let arch = options
+        .arch
+        .clone()
+        .or_else(|| inferred.as_ref().map(|(_, _, arch)| arch.clone()))
+        .unwrap_or_else(|| host_archive_arch().to_string());
+
+    let normalized = normalize_version(&version);
+    let is_host = os == host_go_os() && arch == host_archive_arch();
+    let installed = if is_host {
+        get_go_wrapper(&normalized).exists()
+    } else {
+        get_sdk_root(&normalized, &os, &arch)
+            .join(".unpacked-success")
+            .exists()
+    };
+    if installed {
+        info!(
+            "{} Go {} for {}/{} is already installed.",
+            "✓".green().bold(),
+            version.green(),
+            os,
+            arch
+        );
+        return Ok(());
+    }
+
+    info!(
+        "{} {} {}",
+        "Installing Go version:".bold(),
+        version.green(),
+        format!("(from {:?})", archive).dimmed()
+    );
+    info!(
+        "  {} Skipping checksum verification: installing from a local file",
+        "!".yellow().bold()
+    );
+    info!("{}", "Extracting Go SDK...".dimmed());
+    unpack_sdk(archive, &normalized, &os, &arch)
+}
+
+/// Find the nearest file with the given name in the current directory or its parents
+fn find_nearest_file(name: &str) -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Read the version from a `.go-version` file, ignoring blank lines and comments
+fn read_go_version_file(path: &Path) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+}
+
+/// Read the Go version pinned by a `go.mod` file.
+/// A `toolchain` directive takes precedence over the `go` directive.
+fn read_go_mod_version(path: &Path) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    let mut go_directive = None;
+    let mut toolchain_directive = None;
+
+    for line in contents.lines() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if let Some(toolchain) = line.strip_prefix("toolchain ") {
+            let toolchain = toolchain.trim();
+            if toolchain != "default" {
+                toolchain_directive = Some(extract_version_number(toolchain).to_string());
+            }
+        } else if let Some(go) = line.strip_prefix("go ") {
+            go_directive = Some(go.trim().to_string());
+        }
+    }
+
+    toolchain_directive.or(go_directive)
+}
+
+/// Resolve a `major.minor` go.mod version like `1.22` to the newest installed
+/// `1.22.x`, or to `1.22.0` when no patch release of that line is installed
+fn resolve_go_mod_version(version: &str) -> String {
+    let Some(line) = version.parse::<Version>().ok().filter(Version::is_minor_only) else {
+        return version.to_string();
+    };
+
+    list_installed_versions()
+        .iter()
+        .filter_map(|v| v.parse::<Version>().ok())
+        .filter(|v| v.in_minor_line(&line))
+        .max()
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| format!("{}.0", version))
+}
+
+/// Work out which version `gvm use` should switch to when none is given
+fn find_project_version(from_gomod: bool) -> Option<String> {
+    let (file_name, read): (_, fn(&Path) -> Option<String>) = if from_gomod {
+        ("go.mod", read_go_mod_version)
+    } else {
+        (".go-version", read_go_version_file)
+    };
+
+    let Some(path) = find_nearest_file(file_name) else {
+        report!(
+            "{} No version given and no {} file found.",
+            "Error:".red().bold(),
+            file_name
+        );
+        report!(
+            "Run {} or add a {} file to your project.",
+            "gvm use <version>".cyan(),
+            file_name
+        );
+        return None;
+    };
+    let Some(version) = read(&path) else {
+        report!(
+            "{} No version found in {:?}",
+            "Error:".red().bold(),
+            path
+        );
+        return None;
+    };
+
+    let version = if from_gomod {
+        resolve_go_mod_version(&version)
+    } else {
+        version
+    };
+    report!("{}", format!("Found {} in {:?}", version, path).dimmed());
+    Some(version)
+}
+
+/// Ask a yes/no question on stdin, defaulting to no. When stdin isn't a
+/// terminal nobody can answer, so the answer is no.
+fn confirm(prompt: &str) -> bool {
+    if !io::stdin().is_terminal() {
+        return false;
+    }
+
+    print!("{} [y/N] ", prompt);
+    let _ = io::stdout().flush();
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Ask before deleting SDKs, unless `--yes` was given
+fn confirm_removal(prompt: &str, yes: bool) -> bool {
+    if yes {
+        return true;
+    }
+    if !io::stdin().is_terminal() {
+        report!(
+            "{} Refusing to remove anything without confirmation; pass {} to skip the prompt.",
+            "Error:".red().bold(),
+            "--yes".cyan()
+        );
+        return false;
+    }
+    if !confirm(prompt) {
+        println!("Aborted.");
+        return false;
+    }
+    true
+}
+
+/// The installed version `gvm use` should switch to: `version` itself when
+/// it's installed, otherwise the installed version `best_partial_match` picks
+/// for a partial one like `1.22` or `1`
+fn best_installed_match(version: &str) -> Option<String> {
+    let normalized = normalize_version(version);
+    if is_fully_installed(&normalized) {
+        return Some(normalized);
+    }
+
+    let installed: Vec<String> = list_installed_versions()
+        .into_iter()
+        .filter(|v| is_fully_installed(v))
+        .collect();
+    best_partial_match(version, installed.iter().map(String::as_str)).map(str::to_string)
+}
+
+/// Whether `key` is a name every shell accepts for a variable:
+/// `[A-Za-z_][A-Za-z0-9_]*`
+fn is_env_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parse a `KEY=VALUE` given to `gvm use --set`
+fn parse_env_assignment(assignment: &str) -> Result<(String, String), String> {
+    match assignment.split_once('=') {
+        Some((key, value)) if is_env_key(key) => Ok((key.to_string(), value.to_string())),
+        Some(_) => Err("KEY must be letters, digits and underscores, not starting with a digit"
+            .to_string()),
+        None => Err("expected KEY=VALUE".to_string()),
+    }
+}
+
+/// Quote `value` as one literal word for `shell`, so output that gets eval'd
+/// can't be broken out of
+fn shell_quote(shell: EnvShell, value: &str) -> String {
+    match shell {
+        EnvShell::Bash | EnvShell::Zsh => format!("'{}'", value.replace('\'', "'\\''")),
+        EnvShell::Fish => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'")),
+        EnvShell::Powershell => format!("'{}'", value.replace('\'', "''")),
+    }
+}
+
+/// The line that sets `key` to `value` in `shell`'s syntax
+fn shell_export(shell: EnvShell, key: &str, value: &str) -> String {
+    let value = shell_quote(shell, value);
+    match shell {
+        EnvShell::Fish => format!("set -gx {} {}", key, value),
+        EnvShell::Powershell => format!("$env:{} = {}", key, value),
+        EnvShell::Bash | EnvShell::Zsh => format!("export {}={}", key, value),
+    }
+}
+
+/// The line that removes `key` from `shell`'s environment
+fn shell_unset(shell: EnvShell, key: &str) -> String {
+    match shell {
+        EnvShell::Fish => format!("set -e {}", key),
+        EnvShell::Powershell => format!("Remove-Item Env:{} -ErrorAction SilentlyContinue", key),
+        EnvShell::Bash | EnvShell::Zsh => format!("unset {}", key),
+    }
+}
+
+/// The line that puts `dir` first on `shell`'s PATH
+fn shell_prepend_path(shell: EnvShell, dir: &Path) -> String {
+    match shell {
+        EnvShell::Fish => format!("set -gx PATH \"{}\" $PATH", dir.display()),
+        EnvShell::Powershell => format!(
+            "$env:PATH = \"{}\" + [IO.Path]::PathSeparator + $env:PATH",
+            dir.display()
+        ),
+        EnvShell::Bash | EnvShell::Zsh => format!("export PATH=\"{}:$PATH\"", dir.display()),
+    }
+}
+
+/// Lists the variables gvm last exported in this shell, so the next switch
+/// clears only those and leaves the user's own settings alone
+const ENV_KEYS_VAR: &str = "GVM_ENV_KEYS";
+
+/// Print the lines that set a version's variables, clearing the ones gvm
+/// set for the previous version that this one doesn't
+fn print_env_overlay(shell: EnvShell, overlay: &BTreeMap<String, String>) {
+    let previous = std::env::var(ENV_KEYS_VAR).unwrap_or_default();
+    let stale: BTreeSet<&str> = previous
+        .split_whitespace()
+        .filter(|key| is_env_key(key) && !overlay.contains_key(*key))
+        .collect();
+    for key in stale {
+        println!("{}", shell_unset(shell, key));
+    }
+    for (key, value) in overlay {
+        println!("{}", shell_export(shell, key, value));
+    }
+    if !overlay.is_empty() {
+        let keys: Vec<&str> = overlay.keys().map(String::as_str).collect();
+        println!("{}", shell_export(shell, ENV_KEYS_VAR, &keys.join(" ")));
+    } else if !previous.is_empty() {
+        println!("{}", shell_unset(shell, ENV_KEYS_VAR));
+    }
+}
+
+/// Variables recorded with `gvm use --set` for a version. Keys a hand-edited
+/// config gives that aren't valid names are left out.
+fn env_overlay(version_num: &str) -> BTreeMap<String, String> {
+    let mut overlay = load_config().env.remove(version_num).unwrap_or_default();
+    overlay.retain(|key, _| is_env_key(key));
+    overlay
+}
+
+/// Record `gvm use --set`/`--unset` for a version in the config
+fn update_env_overlay(
+    version_num: &str,
+    set_env: &[(String, String)],
+    unset_env: &[String],
+    shell: bool,
+) -> CmdResult {
+    let mut config = load_config();
+    let overlay = config.env.entry(version_num.to_string()).or_default();
+    for key in unset_env {
+        overlay.remove(key);
+    }
+    for (key, value) in set_env {
+        overlay.insert(key.clone(), value.clone());
+    }
+    if overlay.is_empty() {
+        config.env.remove(version_num);
+    }
+
+    if let Err(e) = save_config(&config) {
+        report!("{} {}", "Error:".red().bold(), e);
+        return Err(Failure::Failed);
+    }
+    // In --shell mode stdout is being eval'd
+    if !shell {
+        for (key, value) in set_env {
+            info!("{}", format!("Go {} sets {}={}", version_num, key, value).dimmed());
+        }
+        for key in unset_env {
+            info!("{}", format!("Go {} no longer sets {}", version_num, key).dimmed());
+        }
+    }
+    Ok(())
+}
+
+/// Print the commands that point this shell's GOROOT and PATH at one version
+fn print_shell_use(normalized: &str) -> CmdResult {
+    let goroot = get_goroot(normalized)
+        .filter(|goroot| goroot.join("bin").join(format!("go{}", EXE_SUFFIX)).is_file());
+    let Some(goroot) = goroot else {
+        report!(
+            "{} Couldn't find the SDK for Go {}.",
+            "Error:".red().bold(),
+            extract_version_number(normalized)
+        );
+        report!(
+            "Run {} to repair it.",
+            format!("gvm reinstall {}", extract_version_number(normalized)).cyan()
+        );
+        return Err(Failure::Failed);
+    };
+    let bin = goroot.join("bin");
+
+    let shell = EnvShell::detect();
+    println!("{}", shell_export(shell, "GOROOT", &goroot.to_string_lossy()));
+    println!("{}", shell_prepend_path(shell, &bin));
+    print_env_overlay(shell, &env_overlay(extract_version_number(normalized)));
+    Ok(())
+}
+
+/// `gvm use system`: remove gvm's `go` link so PATH falls through to the Go
+/// installed outside gvm. With `--shell`, also print the commands that take
+/// the SDK a previous `gvm use --shell` put first off this shell's PATH. The
+/// link's directory stays on PATH, since it holds the user's own tools too.
+fn use_system_go(shell: bool) -> CmdResult {
+    let go_link = get_go_link();
+    let previous = get_current_version();
+    if go_link.is_symlink() || go_link.exists() {
+        if let Err(e) = fs::remove_file(&go_link) {
+            report!(
+                "{} Failed to remove {:?}: {}",
+                "Error:".red().bold(),
+                go_link,
+                e
+            );
+            return Err(Failure::Failed);
+        }
+    }
+    save_current_version(None);
+    record_switch(
+        "use",
+        "system",
+        previous.as_deref().map(extract_version_number),
+    );
+
+    if shell {
+        let sdk_dir = get_sdk_dir();
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        let dirs: Vec<_> = std::env::split_paths(&path)
+            .filter(|dir| !dir.starts_with(&sdk_dir))
+            .collect();
+        let shell = EnvShell::detect();
+        println!("{}", shell_unset(shell, "GOROOT"));
+        if let EnvShell::Fish = shell {
+            let dirs: Vec<_> = dirs
+                .iter()
+                .map(|dir| shell_quote(shell, &dir.to_string_lossy()))
+                .collect();
+            println!("set -gx PATH {}", dirs.join(" "));
+        } else {
+            let path = std::env::join_paths(&dirs).map_err(|e| {
+                report!("{} Failed to build PATH: {}", "Error:".red().bold(), e);
+                Failure::Failed
+            })?;
+            println!("{}", shell_export(shell, "PATH", &path.to_string_lossy()));
+        }
+        return Ok(());
+    }
+
+    match find_system_go() {
+        Some(go) => {
+            info!(
+                "{} Now using the system Go at {}",
+                "✓".green().bold(),
+                go.display()
+            );
+            if let Ok(output) = Command::new(&go).arg("version").output() {
+                if output.status.success() {
+                    info!("{}", String::from_utf8_lossy(&output.stdout).trim().dimmed());
+                }
+            }
+        }
+        None => {
+            report!(
+                "{} Removed gvm's go link, but no Go outside of gvm was found on PATH.",
+                "Warning:".yellow().bold()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn cmd_use(
+    version: Option<&str>,
+    from_gomod: bool,
+    shell: bool,
+    set_env: &[(String, String)],
+    unset_env: &[String],
+) -> CmdResult {
+    let from_file = version.is_none();
+    let version = match version {
+        Some(version) => version.to_string(),
+        None => match find_project_version(from_gomod) {
+            Some(version) => version,
+            None => return Err(Failure::Failed),
+        },
+    };
+
+    if version == "system" {
+        if !set_env.is_empty() || !unset_env.is_empty() {
+            report!(
+                "{} --set and --unset don't apply to the system Go.",
+                "Error:".red().bold()
+            );
+            return Err(Failure::Failed);
+        }
+        return use_system_go(shell);
+    }
+
+    let version = resolve_alias(&version)?;
+    let mut installed = best_installed_match(&version);
+
+    // Offer to install versions requested by a .go-version or go.mod file.
+    // Not in --shell mode, where stdout is being eval'd.
+    let requested = extract_version_number(&version);
+    if installed.is_none()
+        && from_file
+        && !shell
+        && confirm(&format!("Go {} is not installed. Install it now?", requested))
+    {
+        cmd_install(&version, &InstallArgs::default().with_defaults())?;
+        installed = best_installed_match(&version);
+    }
+
+    let Some(normalized) = installed else {
+        if get_go_wrapper(&normalize_version(&version)).exists() {
+            print_missing_sdk(requested);
+            return Err(Failure::NotInstalled);
+        }
+        report!(
+            "{} Go {} is not installed.",
+            "Error:".red().bold(),
+            requested
+        );
+        let versions = list_installed_versions();
+        if versions.is_empty() {
+            report!(
+                "Run {} to install it first.",
+                format!("gvm install {}", requested).cyan()
+            );
+        } else {
+            let versions: Vec<_> = versions.iter().map(|v| extract_version_number(v)).collect();
+            report!("Installed versions: {}", versions.join(", "));
+            report!(
+                "Run {} to install it.",
+                format!("gvm install {}", requested).cyan()
+            );
+        }
+        return Err(Failure::NotInstalled);
+    };
+    let version_num = extract_version_number(&normalized);
+    let go_wrapper = get_go_wrapper(&normalized);
+    let go_link = get_go_link();
+
+    if !set_env.is_empty() || !unset_env.is_empty() {
+        update_env_overlay(version_num, set_env, unset_env, shell)?;
+    }
+    if shell {
+        return print_shell_use(&normalized);
+    }
+
+    let previous = get_current_version();
+    match switch_go_link(&go_wrapper, &go_link) {
+        Ok(_) => {
+            // Verify by running go version
+            let version_output = match Command::new(&go_link).arg("version").output() {
+                Ok(output) if output.status.success() => {
+                    String::from_utf8_lossy(&output.stdout).trim().to_string()
+                }
+                result => {
+                    report!(
+                        "{} Go {} appears to be broken: `go version` failed.",
+                        "Error:".red().bold(),
+                        version_num
+                    );
+                    match result {
+                        Ok(output) => {
+                            let stderr = String::from_utf8_lossy(&output.stderr);
+                            if !stderr.trim().is_empty() {
+                                report!("  {}", stderr.trim());
+                            }
+                        }
+                        Err(e) => report!("  {}", e),
+                    }
+                    restore_go_link(previous.as_deref(), &go_link);
+                    report!(
+                        "Run {} to repair it.",
+                        format!("gvm reinstall {}", version_num).cyan()
+                    );
+                    return Err(Failure::Failed);
+                }
+            };
+
+            save_current_version(Some(&normalized));
+            record_switch(
+                "use",
+                version_num,
+                previous.as_deref().map(extract_version_number),
+            );
+            info!(
+                "{} Now using Go {}",
+                "✓".green().bold(),
+                version_num.green()
+            );
+            info!("{}", version_output.dimmed());
+
+            if let Some(goroot) = get_goroot(&normalized) {
+                info!("{}", format!("GOROOT: {}", goroot.display()).dimmed());
+                if std::env::var_os("GOROOT").is_some_and(|current| current != goroot.as_os_str()) {
+                    report!(
+                        "{} GOROOT is set to another SDK in this shell; run {} to update it.",
+                        "Warning:".yellow().bold(),
+                        "eval \"$(gvm env)\"".cyan()
+                    );
+                }
+            }
+
+            let link_dir = get_link_dir();
+            if !is_on_path(&link_dir) {
+                report!(
+                    "{} {:?} is not on your PATH, so `go` may still resolve to another install.",
+                    "Warning:".yellow().bold(),
+                    link_dir
+                );
+                report!(
+                    "Run {} for the line to add to your shell profile.",
+                    "gvm env".cyan()
+                );
+            }
+            if let Some(setting) = go_toolchain(&go_link).filter(|s| toolchain_auto_switches(s)) {
+                report!(
+                    "{}",
+                    format!(
+                        "Note: GOTOOLCHAIN={}, so a go.mod that needs a newer Go makes `go` \
+                         download and run that instead of {}.",
+                        setting, version_num
+                    )
+                    .dimmed()
+                );
+                report!(
+                    "{}",
+                    "Run `go env -w GOTOOLCHAIN=local` to always use the version gvm selects."
+                        .dimmed()
+                );
+            }
+            run_post_use_hook(version_num);
+            Ok(())
+        }
+        Err(e) => {
+            report!(
+                "{} Failed to create symlink: {}",
+                "Error:".red().bold(),
+                e
+            );
+            Err(Failure::Failed)
+        }
+    }
+}
+
+/// Run the post-use hook from `GVM_POST_USE_HOOK` or the config through the
+/// shell, with the new version appended as its argument. A failing hook is
+/// reported but doesn't undo the switch.
+fn run_post_use_hook(version_num: &str) {
+    let Some(hook) = std::env::var("GVM_POST_USE_HOOK")
+        .ok()
+        .or_else(|| load_config().post_use_hook)
+        .filter(|hook| !hook.trim().is_empty())
+    else {
+        return;
+    };
+
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(format!("{} {}", hook, version_num));
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(format!("{} \"$@\"", hook))
+            .arg("sh")
+            .arg(version_num);
+        command
+    };
+    log_command(&command);
+    match command.status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => report!(
+            "{} Post-use hook `{}` failed with exit code: {:?}",
+            "Warning:".yellow().bold(),
+            hook,
+            status.code()
+        ),
+        Err(e) => report!(
+            "{} Failed to run post-use hook `{}`: {}",
+            "Warning:".yellow().bold(),
+            hook,
+            e
+        ),
+    }
+}
+
+/// Point the `go` link back at the version that was active before a failed
+/// switch, or remove it if none was
+fn restore_go_link(previous: Option<&str>, go_link: &Path) {
+    let restored = match previous {
+        Some(previous) => switch_go_link(&get_go_wrapper(previous), go_link),
+        None => fs::remove_file(go_link),
+    };
+    if restored.is_ok() {
+        save_current_version(previous);
+    }
+    match (restored, previous) {
+        (Ok(()), Some(previous)) => report!(
+            "Switched back to Go {}.",
+            extract_version_number(previous)
+        ),
+        (Ok(()), None) => {}
+        (Err(e), _) => report!(
+            "{} Failed to restore the previous `go` link: {}",
+            "Warning:".yellow().bold(),
+            e
+        ),
+    }
+}
+
+fn cmd_default(version: Option<&str>) -> CmdResult {
+    let mut config = load_config();
+
+    let Some(version) = version else {
+        // Re-apply the saved default
+        let Some(default) = config.default else {
+            report!("{} No default version set.", "Error:".red().bold());
+            report!(
+                "Run {} to choose one.",
+                "gvm default <version>".cyan()
+            );
+            return Err(Failure::Failed);
+        };
+        return cmd_use(Some(&default), false, false, &[], &[]);
+    };
+
+    cmd_use(Some(version), false, false, &[], &[])?;
+
+    // Aliases are saved by name so the default follows them when they move
+    let (saved, label) = if is_alias(version) {
+        (version.to_string(), format!("alias {}", version.cyan()))
+    } else {
+        let version_num = extract_version_number(&normalize_version(version)).to_string();
+        let label = format!("Go {}", version_num.green());
+        (version_num, label)
+    };
+    let previous = config.default.replace(saved.clone());
+    if let Err(e) = save_config(&config) {
+        report!("{} {}", "Error:".red().bold(), e);
+        return Err(Failure::Failed);
+    }
+    record_switch("default", &saved, previous.as_deref());
+    info!("{} Default set to {}", "✓".green().bold(), label);
+    Ok(())
+}
+
+/// Whether an alias name could be mistaken for a version number
+fn looks_like_version(name: &str) -> bool {
+    extract_version_number(name)
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_digit())
+}
+
+fn cmd_alias(name: Option<&str>, version: Option<&str>, delete: bool) -> CmdResult {
+    let mut config = load_config();
+
+    let Some(name) = name else {
+        if config.aliases.is_empty() {
+            println!("{}", "No aliases defined.".yellow());
             println!(
-                "{} Now using Go {}",
-                "✓".green().bold(),
-                version_num.green()
+                "Run {} to define one.",
+                "gvm alias <name> <version>".cyan()
+            );
+        } else {
+            println!("{}", "Aliases:".bold());
+            for (name, version) in &config.aliases {
+                println!("  {} -> {}", name.cyan(), version);
+            }
+        }
+        return Ok(());
+    };
+
+    if BUILTIN_ALIASES.contains(&name) {
+        report!(
+            "{} {} is a built-in alias and can't be changed.",
+            "Error:".red().bold(),
+            name
+        );
+        return Err(Failure::Failed);
+    }
+
+    if delete {
+        if config.aliases.remove(name).is_none() {
+            report!("{} No alias named {}.", "Error:".red().bold(), name);
+            return Err(Failure::Failed);
+        }
+        if let Err(e) = save_config(&config) {
+            report!("{} {}", "Error:".red().bold(), e);
+            return Err(Failure::Failed);
+        }
+        println!("{} Removed alias {}", "✓".green().bold(), name.cyan());
+        return Ok(());
+    }
+
+    if looks_like_version(name) {
+        report!(
+            "{} Alias {} looks like a version number; pick a name such as lts.",
+            "Error:".red().bold(),
+            name
+        );
+        return Err(Failure::Failed);
+    }
+
+    let Some(version) = version else {
+        let Some(version) = config.aliases.get(name) else {
+            report!("{} No alias named {}.", "Error:".red().bold(), name);
+            return Err(Failure::Failed);
+        };
+        println!("{}", version);
+        return Ok(());
+    };
+
+    let version_num = extract_version_number(version).to_string();
+    config.aliases.insert(name.to_string(), version_num.clone());
+    if let Err(e) = save_config(&config) {
+        report!("{} {}", "Error:".red().bold(), e);
+        return Err(Failure::Failed);
+    }
+    println!(
+        "{} {} now points to Go {}",
+        "✓".green().bold(),
+        name.cyan(),
+        version_num.green()
+    );
+    Ok(())
+}
+
+/// Finds the nearest .go-version from the shell itself, so a prompt only
+/// runs gvm when the wanted version changes
+const POSIX_HOOK: &str = r#"_gvm_hook() {
+  local dir="$PWD" version="" output
+  while :; do
+    if [ -f "$dir/.go-version" ]; then
+      read -r version < "$dir/.go-version"
+      break
+    fi
+    [ -z "$dir" ] || [ "$dir" = "/" ] && break
+    dir="${dir%/*}"
+  done
+  version="${version#"${version%%[![:space:]]*}"}"
+  version="${version%"${version##*[![:space:]]}"}"
+  if [ -n "$version" ] && [ "$version" != "$_GVM_HOOK_VERSION" ]; then
+    output="$(SHELL=sh gvm use --shell "$version")" || return
+    if [ -n "$_GVM_HOOK_BIN" ]; then
+      PATH=":$PATH:"
+      PATH="${PATH//:$_GVM_HOOK_BIN:/:}"
+      PATH="${PATH#:}"
+      PATH="${PATH%:}"
+    fi
+    eval "$output"
+    _GVM_HOOK_VERSION="$version"
+    _GVM_HOOK_BIN="$GOROOT/bin"
+  fi
+}
+"#;
+
+const BASH_HOOK: &str = r#"case ";$PROMPT_COMMAND;" in
+  *";_gvm_hook;"*) ;;
+  *) PROMPT_COMMAND="_gvm_hook${PROMPT_COMMAND:+;$PROMPT_COMMAND}" ;;
+esac
+"#;
+
+const ZSH_HOOK: &str = r#"autoload -Uz add-zsh-hook
+add-zsh-hook precmd _gvm_hook
+"#;
+
+const FISH_HOOK: &str = r#"function _gvm_hook --on-variable PWD
+  set -l dir $PWD
+  set -l version
+  while true
+    if test -f "$dir/.go-version"
+      read -l line < "$dir/.go-version"
+      set version (string trim -- $line)
+      break
+    end
+    if test -z "$dir"; or test "$dir" = /
+      break
+    end
+    set dir (string replace -r '/[^/]*$' '' -- $dir)
+  end
+  if test -n "$version"; and test "$version" != "$_gvm_hook_version"
+    set -l output (SHELL=fish gvm use --shell $version); or return
+    if set -q _gvm_hook_bin; and contains -- $_gvm_hook_bin $PATH
+      set -e PATH[(contains -i -- $_gvm_hook_bin $PATH)]
+    end
+    string join \n -- $output | source
+    set -g _gvm_hook_version $version
+    set -g _gvm_hook_bin $GOROOT/bin
+  end
+end
+_gvm_hook
+"#;
+
+fn cmd_hook(shell: HookShell) -> CmdResult {
+    match shell {
+        HookShell::Bash => print!("{}{}", POSIX_HOOK, BASH_HOOK),
+        HookShell::Zsh => print!("{}{}", POSIX_HOOK, ZSH_HOOK),
+        HookShell::Fish => print!("{}", FISH_HOOK),
+    }
+    Ok(())
+}
+
+/// Subcommands whose first argument is an installed version
+const VERSION_COMMANDS: &[&str] = &[
+    "use",
+    "uninstall",
+    "reinstall",
+    "which",
+    "run",
+    "default",
+    "pin",
+    "unpin",
+];
+
+/// Completion scripts are written by hand like the hooks, with the
+/// subcommand list taken from clap so it can't drift. Versions come from
+/// `gvm __complete_versions` at completion time.
+fn cmd_completions(shell: HookShell) -> CmdResult {
+    let cli = Cli::command();
+    let subcommands: Vec<(&str, String)> = cli
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .flat_map(|sub| {
+            let about = sub
+                .get_about()
+                .map(|about| about.to_string())
+                .unwrap_or_default();
+            std::iter::once(sub.get_name())
+                .chain(sub.get_visible_aliases())
+                .map(move |name| (name, about.clone()))
+        })
+        .collect();
+    let names: Vec<&str> = subcommands.iter().map(|(name, _)| *name).collect();
+
+    match shell {
+        HookShell::Bash => {
+            println!("_gvm_completions() {{");
+            println!("  local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"");
+            println!("  if [ \"$COMP_CWORD\" -eq 1 ]; then");
+            println!(
+                "    COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+                names.join(" ")
+            );
+            println!("  elif [ \"$COMP_CWORD\" -eq 2 ]; then");
+            println!("    case \"${{COMP_WORDS[1]}}\" in");
+            println!("      {})", VERSION_COMMANDS.join("|"));
+            println!("        COMPREPLY=($(compgen -W \"$(gvm __complete_versions 2>/dev/null)\" -- \"$cur\")) ;;");
+            println!("    esac");
+            println!("  fi");
+            println!("}}");
+            println!("complete -F _gvm_completions gvm");
+        }
+        HookShell::Zsh => {
+            println!("_gvm() {{");
+            println!("  if (( CURRENT == 2 )); then");
+            println!("    local -a commands");
+            println!("    commands=(");
+            for (name, about) in &subcommands {
+                println!("      '{}:{}'", name, about.replace('\'', "'\\''"));
+            }
+            println!("    )");
+            println!("    _describe 'command' commands");
+            println!("  elif (( CURRENT == 3 )); then");
+            println!("    case $words[2] in");
+            println!("      {})", VERSION_COMMANDS.join("|"));
+            println!("        local -a versions");
+            println!("        versions=(${{(f)\"$(gvm __complete_versions 2>/dev/null)\"}})");
+            println!("        _describe 'version' versions ;;");
+            println!("    esac");
+            println!("  fi");
+            println!("}}");
+            println!("compdef _gvm gvm");
+        }
+        HookShell::Fish => {
+            println!("complete -c gvm -f");
+            for (name, about) in &subcommands {
+                println!(
+                    "complete -c gvm -n __fish_use_subcommand -a {} -d '{}'",
+                    name,
+                    about.replace('\'', "\\'")
+                );
+            }
+            println!(
+                "complete -c gvm -n '__fish_seen_subcommand_from {}' -a '(gvm __complete_versions 2>/dev/null)'",
+                VERSION_COMMANDS.join(" ")
+            );
+        }
+    }
+    Ok(())
+}
+
+fn cmd_complete_versions() -> CmdResult {
+    for version in list_installed_versions() {
+        println!("{}", extract_version_number(&version));
+    }
+    Ok(())
+}
+
+fn cmd_env(shell: EnvShell) -> CmdResult {
+    let link_dir = get_link_dir();
+    let current = get_current_version();
+    let goroot = current.as_deref().and_then(get_goroot);
+
+    println!("{}", shell_prepend_path(shell, &link_dir));
+
+    // Tools like gopls read GOROOT directly instead of asking `go`
+    if let Some(goroot) = goroot {
+        println!("{}", shell_export(shell, "GOROOT", &goroot.to_string_lossy()));
+    }
+
+    // Variables the previous version set are cleared, so re-running this
+    // after a switch doesn't leave them behind
+    let current = current.as_deref().map(extract_version_number);
+    print_env_overlay(shell, &current.map(env_overlay).unwrap_or_default());
+    Ok(())
+}
+
+fn cmd_which(version: &str, goroot: bool) -> CmdResult {
+    let version = resolve_alias(version)?;
+    let normalized = normalize_version(&version);
+    let version_num = extract_version_number(&normalized);
+    let go_wrapper = get_go_wrapper(&normalized);
+
+    if !go_wrapper.exists() {
+        report!(
+            "{} Go {} is not installed.",
+            "Error:".red().bold(),
+            version_num
+        );
+        return Err(Failure::NotInstalled);
+    }
+
+    if !goroot {
+        println!("{}", go_wrapper.display());
+        return Ok(());
+    }
+
+    // The wrapper reports where its SDK should be even before `download`
+    // has put anything there, so check that it's really present
+    match get_goroot(&normalized) {
+        Some(goroot) if goroot.join("bin").join(format!("go{}", EXE_SUFFIX)).is_file() => {
+            println!("{}", goroot.display());
+            Ok(())
+        }
+        _ => {
+            print_missing_sdk(version_num);
+            Err(Failure::NotInstalled)
+        }
+    }
+}
+
+fn cmd_run(version: &str, args: &[String]) -> CmdResult {
+    let version = resolve_alias(version)?;
+    let normalized = normalize_version(&version);
+    let version_num = extract_version_number(&normalized);
+    let go_wrapper = get_go_wrapper(&normalized);
+
+    if !go_wrapper.exists() {
+        report!(
+            "{} Go {} is not installed.",
+            "Error:".red().bold(),
+            version_num
+        );
+        report!(
+            "Run {} to install it first.",
+            format!("gvm install {}", version_num).cyan()
+        );
+        return Err(Failure::NotInstalled);
+    }
+    if !is_fully_installed(&normalized) {
+        print_missing_sdk(version_num);
+        return Err(Failure::NotInstalled);
+    }
+
+    let mut command = Command::new(&go_wrapper);
+    command.args(args).envs(env_overlay(version_num));
+    log_command(&command);
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(Failure::Child(status.code().unwrap_or(1))),
+        Err(e) => {
+            report!(
+                "{} Failed to run {:?}: {}",
+                "Error:".red().bold(),
+                go_wrapper,
+                e
+            );
+            Err(Failure::Failed)
+        }
+    }
+}
+
+fn cmd_exec(command: &[String]) -> CmdResult {
+    let link_dir = get_link_dir();
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let path = std::env::join_paths(
+        std::iter::once(link_dir).chain(std::env::split_paths(&path)),
+    )
+    .map_err(|e| {
+        report!("{} Failed to build PATH: {}", "Error:".red().bold(), e);
+        Failure::Failed
+    })?;
+
+    let overlay = get_current_version()
+        .map(|v| env_overlay(extract_version_number(&v)))
+        .unwrap_or_default();
+
+    let (program, args) = command.split_first().ok_or(Failure::Failed)?;
+    let mut command = Command::new(program);
+    command.args(args).env("PATH", path).envs(overlay);
+    log_command(&command);
+    match command.status() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(Failure::Child(status.code().unwrap_or(1))),
+        Err(e) => {
+            report!(
+                "{} Failed to run {}: {}",
+                "Error:".red().bold(),
+                program,
+                e
+            );
+            Err(Failure::Failed)
+        }
+    }
+}
+
+fn cmd_current(json: bool) -> CmdResult {
+    let current = get_current_version();
+    let version_num = current.as_deref().map(extract_version_number);
+
+    if json {
+        print_json(&CurrentOutput {
+            current: version_num.map(str::to_string),
+        });
+    } else if let Some(version_num) = version_num {
+        println!("{}", version_num);
+    }
+
+    match current {
+        Some(_) => Ok(()),
+        None => Err(Failure::Failed),
+    }
+}
+
+fn cmd_status(json: bool) -> CmdResult {
+    let current = get_current_version().filter(|_| !is_go_link_dangling());
+    let installed = list_installed_versions();
+    // A stale cached list is fine for a summary; offline it's just unknown
+    let latest = get_releases(false, true)
+        .ok()
+        .and_then(|releases| stable_versions(&releases).max())
+        .map(|v| v.to_string());
+    let link_dir = get_link_dir();
+    let on_path = is_on_path(&link_dir);
+
+    if json {
+        print_json(&StatusOutput {
+            current: current.as_deref().map(extract_version_number).map(str::to_string),
+            installed: installed.len(),
+            latest_stable: latest,
+            link_dir,
+            link_dir_on_path: on_path,
+            config_file: get_config_file(),
+        });
+        return Ok(());
+    }
+
+    let current_text = match &current {
+        Some(version) => extract_version_number(version).green().to_string(),
+        None => "none".dimmed().to_string(),
+    };
+    println!("{:<11}{}", "Current:".bold(), current_text);
+    let count = match installed.len() {
+        1 => "1 version".to_string(),
+        n => format!("{} versions", n),
+    };
+    println!("{:<11}{}", "Installed:".bold(), count);
+    let latest_text = match &latest {
+        Some(latest) if installed.contains(&normalize_version(latest)) => {
+            format!("{} {}", latest, "(installed)".dimmed())
+        }
+        Some(latest) => format!("{} {}", latest, "(not installed)".yellow()),
+        None => "unknown (couldn't fetch the release list)".dimmed().to_string(),
+    };
+    println!("{:<11}{}", "Latest:".bold(), latest_text);
+    let path_text = if on_path {
+        format!("{} {}", link_dir.display(), "is on PATH".green())
+    } else {
+        format!(
+            "{} {} (see {})",
+            link_dir.display(),
+            "is not on PATH".red(),
+            "gvm env".cyan()
+        )
+    };
+    println!("{:<11}{}", "PATH:".bold(), path_text);
+    println!("{:<11}{}", "Config:".bold(), get_config_file().display());
+    Ok(())
+}
+
+/// Whether a release asset is a gvm binary for this OS and architecture,
+/// e.g. `gvm-x86_64-unknown-linux-gnu` or `gvm-macos-arm64`
+fn is_host_asset(name: &str) -> bool {
+    let name = name.to_lowercase();
+    let os_names: &[&str] = match std::env::consts::OS {
+        "macos" => &["macos", "darwin", "apple"],
+        "linux" => &["linux"],
+        "windows" => &["windows"],
+        _ => &[],
+    };
+    let arch_names: &[&str] = match std::env::consts::ARCH {
+        "x86_64" => &["x86_64", "amd64"],
+        "aarch64" => &["aarch64", "arm64"],
+        _ => &[],
+    };
+
+    let is_binary = if EXE_SUFFIX.is_empty() {
+        !name.contains(".tar") && !name.ends_with(".zip") && !name.ends_with(".sha256")
+    } else {
+        name.ends_with(EXE_SUFFIX)
+    };
+    is_binary
+        && os_names.iter().any(|os| name.contains(os))
+        && arch_names.iter().any(|arch| name.contains(arch))
+}
+
+/// Swap the running executable for `new_exe`. On Unix a rename over it is
+/// atomic; Windows won't replace a running binary, so it's moved aside first.
+fn replace_current_exe(new_exe: &Path, exe: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(new_exe, fs::Permissions::from_mode(0o755))?;
+        fs::rename(new_exe, exe)
+    }
+
+    #[cfg(windows)]
+    {
+        let old_exe = exe.with_extension("old.exe");
+        let _ = fs::remove_file(&old_exe);
+        fs::rename(exe, &old_exe)?;
+        fs::rename(new_exe, exe)
+    }
+}
+
+fn cmd_self_update(check_only: bool) -> CmdResult {
+    require_network("self-update")?;
+    let release: GvmRelease = net::get_text(GVM_LATEST_RELEASE_URL)
+        .and_then(|body| {
+            serde_json::from_str(&body)
+                .map_err(|e| format!("Failed to parse release info: {}", e))
+        })
+        .map_err(|e| network_failure(format!("Failed to check for updates: {}", e)))?;
+
+    let current = env!("CARGO_PKG_VERSION");
+    let latest_tag = release.tag_name.trim_start_matches('v');
+    let Ok(latest) = semver::Version::parse(latest_tag) else {
+        report!(
+            "{} Latest release has an unrecognized version: {}",
+            "Error:".red().bold(),
+            release.tag_name
+        );
+        return Err(Failure::Failed);
+    };
+    if semver::Version::parse(current).is_ok_and(|current| latest <= current) {
+        println!("{} gvm {} is up to date.", "✓".green().bold(), current);
+        return Ok(());
+    }
+
+    println!(
+        "A newer gvm is available: {} -> {}",
+        current,
+        latest.to_string().green()
+    );
+    if check_only {
+        println!("Run {} to install it.", "gvm self-update".cyan());
+        return Ok(());
+    }
+
+    let Some(asset) = release.assets.iter().find(|a| is_host_asset(&a.name)) else {
+        report!(
+            "{} No gvm {} binary was published for {}/{}.",
+            "Error:".red().bold(),
+            latest,
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+        report!(
+            "Update with {} instead.",
+            "cargo install --git https://github.com/Yiheng-Liu/gvm.git".cyan()
+        );
+        return Err(Failure::Failed);
+    };
+    let checksum_name = format!("{}.sha256", asset.name);
+    let Some(checksum_asset) = release.assets.iter().find(|a| a.name == checksum_name) else {
+        report!(
+            "{} No published checksum ({}) to verify the download against.",
+            "Error:".red().bold(),
+            checksum_name
+        );
+        return Err(Failure::Failed);
+    };
+
+    let exe = match std::env::current_exe().and_then(fs::canonicalize) {
+        Ok(exe) => exe,
+        Err(e) => {
+            report!(
+                "{} Could not locate the running gvm binary: {}",
+                "Error:".red().bold(),
+                e
+            );
+            return Err(Failure::Failed);
+        }
+    };
+
+    let expected = net::get_text(&checksum_asset.browser_download_url)
+        .map_err(|e| network_failure(format!("Failed to download {}: {}", checksum_name, e)))?;
+    let Some(expected) = expected.split_whitespace().next().map(str::to_lowercase) else {
+        report!(
+            "{} No checksum published for {} ({} is empty).",
+            "Error:".red().bold(),
+            asset.name,
+            checksum_name
+        );
+        return Err(Failure::Failed);
+    };
+
+    // Download next to the binary so the final rename stays on one filesystem
+    println!("{}", format!("Downloading {}...", asset.name).dimmed());
+    let new_exe = exe.with_file_name(format!(".gvm-update{}", EXE_SUFFIX));
+    net::download_file(&asset.browser_download_url, &new_exe).map_err(network_failure)?;
+
+    let actual = match sha256_file(&new_exe) {
+        Ok(actual) => actual,
+        Err(e) => {
+            let _ = fs::remove_file(&new_exe);
+            report!(
+                "{} Could not read the downloaded {}: {}",
+                "Error:".red().bold(),
+                asset.name,
+                e
+            );
+            return Err(Failure::Failed);
+        }
+    };
+    if actual != expected {
+        let _ = fs::remove_file(&new_exe);
+        report!(
+            "{} Checksum mismatch for {}",
+            "Error:".red().bold(),
+            asset.name
+        );
+        report!("  expected: {}", expected);
+        report!("  actual:   {}", actual);
+        return Err(Failure::Failed);
+    }
+    println!("{}", "  ✓ Checksum verified".green());
+
+    if let Err(e) = replace_current_exe(&new_exe, &exe) {
+        let _ = fs::remove_file(&new_exe);
+        report!(
+            "{} Failed to replace {:?}: {}",
+            "Error:".red().bold(),
+            exe,
+            e
+        );
+        return Err(Failure::Failed);
+    }
+
+    println!(
+        "{} Updated gvm {} -> {}",
+        "✓".green().bold(),
+        current,
+        latest.to_string().green()
+    );
+    Ok(())
+}
+
+/// Effective `GOTOOLCHAIN` of a `go` binary, from `go env GOTOOLCHAIN`.
+/// Go releases before 1.21 don't have the setting.
+fn go_toolchain(go: &Path) -> Option<String> {
+    let mut command = Command::new(go);
+    command.args(["env", "GOTOOLCHAIN"]);
+    log_command(&command);
+    let output = command.output().ok().filter(|o| o.status.success())?;
+    let setting = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!setting.is_empty()).then_some(setting)
+}
+
+/// Whether a `GOTOOLCHAIN` setting lets `go` download and run the toolchain a
+/// go.mod asks for (`auto`, `local+auto`, `go1.22.0+auto`, ...), bypassing
+/// the version behind gvm's link
+fn toolchain_auto_switches(setting: &str) -> bool {
+    setting == "auto" || setting.ends_with("+auto")
+}
+
+fn print_check(ok: bool, label: &str, hint: &str) {
+    if ok {
+        println!("  {} {}", "✓".green().bold(), label);
+    } else {
+        println!("  {} {}", "✗".red().bold(), label);
+        println!("      {}", hint.dimmed());
+    }
+}
+
+fn cmd_doctor() -> CmdResult {
+    let bin_dir = get_go_bin_dir();
+    let go_link = get_go_link();
+    let mut all_ok = true;
+    let mut check = |ok: bool, label: &str, hint: &str| {
+        all_ok &= ok;
+        print_check(ok, label, hint);
+    };
+
+    println!("{}", "Checking gvm setup:".bold());
+
+    check(
+        bin_dir.is_dir(),
+        &format!("bin directory {:?} exists", bin_dir),
+        "Install a version with `gvm install <version>` to create it.",
+    );
+
+    let link_dir = get_link_dir();
+    check(
+        is_on_path(&link_dir),
+        &format!("{:?} is on PATH", link_dir),
+        "Add `eval \"$(gvm env)\"` to your shell profile.",
+    );
+
+    let go_on_path = find_on_path("go");
+    // Another manager's `go` ahead of gvm's link is the usual reason
+    // `gvm use` seems to change nothing
+    let from_link_dir = |path: &Path| path.parent().is_some_and(|dir| same_dir(dir, &link_dir));
+    match go_on_path.as_deref() {
+        Some(path) if is_on_path(&link_dir) && shadows_go_link(path, &go_link) => check(
+            false,
+            &format!("`go` resolves to gvm's link (found {:?} from {} first)", path, go_manager(path)),
+            &format!(
+                "Move {:?} ahead of it on PATH, or run `gvm rename-link gvm-go` to leave `go` to it.",
+                link_dir
+            ),
+        ),
+        Some(path) if !from_link_dir(path) => check(
+            true,
+            &format!("`go` resolves to {:?} (from {})", path, go_manager(path)),
+            "",
+        ),
+        Some(path) => check(true, &format!("`go` resolves to {:?}", path), ""),
+        None => check(
+            false,
+            "`go` resolves on PATH",
+            "Run `gvm use <version>` and make sure the bin directory is on PATH.",
+        ),
+    }
+
+    // Resolved like `gvm current`, so a copied `go.exe` on Windows counts too
+    match get_current_version().filter(|_| !is_go_link_dangling()) {
+        Some(version) => check(
+            true,
+            &format!("current version {} is valid", extract_version_number(&version)),
+            "",
+        ),
+        // After `gvm use system` there's no link and PATH's own Go is used
+        None => match find_system_go().filter(|_| go_link.symlink_metadata().is_err()) {
+            Some(go) => check(true, &format!("using the system Go at {}", go.display()), ""),
+            None => check(
+                false,
+                "a current version is selected",
+                "Run `gvm use <version>` to select an installed version.",
+            ),
+        },
+    }
+
+    // Not a failure: auto-switching is Go's default, gvm just can't see it
+    if let Some(setting) = go_toolchain(&go_link).filter(|s| toolchain_auto_switches(s)) {
+        println!(
+            "  {} GOTOOLCHAIN is {}: `go` switches to the toolchain a go.mod asks for",
+            "!".yellow().bold(),
+            setting
+        );
+        println!(
+            "      {}",
+            "A project needing a newer Go runs that instead of gvm's version; \
+             run `go env -w GOTOOLCHAIN=local` to keep gvm in control."
+                .dimmed()
+        );
+    }
+
+    let problems = sdk_problems();
+    check(
+        problems.is_empty(),
+        "each wrapper has its own SDK of the right version",
+        "Run `gvm reinstall <version>` for each version listed below.",
+    );
+    for problem in &problems {
+        println!("      {}", problem);
+    }
+
+    #[cfg(feature = "network")]
+    {
+        let base = download_base();
+        check(
+            net::is_reachable(&base),
+            &format!("{} is reachable", base),
+            "Check your network connection, HTTPS_PROXY, or GVM_DOWNLOAD_BASE.",
+        );
+    }
+
+    println!();
+    if all_ok {
+        println!("{} Everything looks good.", "✓".green().bold());
+        Ok(())
+    } else {
+        println!("{} Some checks failed; see the hints above.", "✗".red().bold());
+        Err(Failure::Failed)
+    }
+}
+
+fn cmd_uninstall(version: &str, force: bool, yes: bool) -> CmdResult {
+    let version = resolve_alias(version)?;
+    let normalized = normalize_version(&version);
+    let version_num = extract_version_number(&normalized);
+
+    let go_wrapper = get_go_wrapper(&normalized);
+    let sdk_dir = get_sdk_dir().join(&normalized);
+
+    // Check if version is installed. An archive install's wrapper is a
+    // symlink into its SDK, so it may be dangling once the SDK is gone.
+    if fs::symlink_metadata(&go_wrapper).is_err() && !sdk_dir.exists() {
+        report!(
+            "{} Go {} is not installed.",
+            "Error:".red().bold(),
+            version_num
+        );
+        // SDKs for other platforms are listed as "SDK only"
+        let prefix = format!("{}.", normalized);
+        let platforms: Vec<String> = fs::read_dir(get_sdk_dir())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name().to_string_lossy().to_string();
+                let (os, arch) = name.strip_prefix(&prefix)?.split_once('-')?;
+                Some(format!("--os {} --arch {}", os, arch))
+            })
+            .collect();
+        match platforms.first() {
+            Some(platform) => report!(
+                "Its SDK for another platform is removed with {}.",
+                format!("gvm uninstall {} {}", version_num, platform).cyan()
+            ),
+            None => report!(
+                "Run {} to see installed versions.",
+                "gvm list".cyan()
+            ),
+        }
+        return Err(Failure::NotInstalled);
+    }
+
+    // Refuse to remove the active version unless forced
+    let is_current = get_current_version().as_deref() == Some(normalized.as_str());
+    if is_current && !force {
+        report!(
+            "{} Go {} is currently in use.",
+            "Error:".red().bold(),
+            version_num
+        );
+        report!(
+            "Switch to another version with {} or pass {} to remove it anyway.",
+            "gvm use <version>".cyan(),
+            "--force".cyan()
+        );
+        return Err(Failure::Failed);
+    }
+
+    let prompt = format!("Remove Go {} and its SDK?", version_num);
+    if !confirm_removal(&prompt, yes) {
+        return Err(Failure::Failed);
+    }
+
+    if !remove_version(&normalized) {
+        return Err(Failure::Failed);
+    }
+
+    // Don't leave a dangling 'go' symlink behind
+    if is_current {
+        save_current_version(None);
+        if let Err(e) = fs::remove_file(get_go_link()) {
+            report!(
+                "{} Failed to remove 'go' symlink: {}",
+                "Error:".red().bold(),
+                e
             );
+        }
+    }
 
-            // Verify by running go version
-            if let Ok(output) = Command::new(&go_link).arg("version").output() {
-                if output.status.success() {
-                    let version_output = String::from_utf8_lossy(&output.stdout);
-                    println!("{}", version_output.trim().dimmed());
-                }
+    info!(
+        "{} Go {} uninstalled.",
+        "✓".green().bold(),
+        version_num.green()
+    );
+    Ok(())
+}
+
+/// Remove an SDK downloaded for another platform; there's no wrapper or
+/// `go` link to go with it
+fn cmd_uninstall_platform(version: &str, os: &str, arch: &str, yes: bool) -> CmdResult {
+    let normalized = normalize_version(&resolve_alias(version)?);
+    let version_num = extract_version_number(&normalized);
+    let sdk_dir = get_sdk_root(&normalized, os, arch);
+
+    if !sdk_dir.exists() {
+        report!(
+            "{} Go {} for {}/{} is not installed.",
+            "Error:".red().bold(),
+            version_num,
+            os,
+            arch
+        );
+        report!(
+            "Run {} to see installed versions.",
+            "gvm list".cyan()
+        );
+        return Err(Failure::NotInstalled);
+    }
+
+    let prompt = format!("Remove the Go {} SDK for {}/{}?", version_num, os, arch);
+    if !confirm_removal(&prompt, yes) {
+        return Err(Failure::Failed);
+    }
+    if !remove_sdk(&sdk_dir) {
+        report!("{}", "Fix the permissions and try again.".yellow());
+        return Err(Failure::Failed);
+    }
+
+    info!(
+        "{} Go {} for {}/{} uninstalled.",
+        "✓".green().bold(),
+        version_num.green(),
+        os,
+        arch
+    );
+    Ok(())
+}
+
+/// Remove every installed release of a minor line. The current version is
+/// skipped with a warning unless `force` is given.
+fn cmd_uninstall_minor(line: &str, force: bool, yes: bool) -> CmdResult {
+    let line_version = match line.parse::<Version>() {
+        Ok(version) if version.is_minor_only() => version,
+        _ => {
+            report!(
+                "{} {} is not a minor line; use something like 1.20.",
+                "Error:".red().bold(),
+                line
+            );
+            return Err(Failure::Failed);
+        }
+    };
+
+    let current = get_current_version();
+    let mut to_remove = Vec::new();
+    for installed in list_installed_versions() {
+        let in_line = installed
+            .parse::<Version>()
+            .is_ok_and(|v| v.major == line_version.major && v.minor == line_version.minor);
+        if !in_line {
+            continue;
+        }
+        if current.as_ref() == Some(&installed) && !force {
+            report!(
+                "{} Skipping Go {}, which is currently in use (pass {} to remove it too).",
+                "Warning:".yellow().bold(),
+                extract_version_number(&installed),
+                "--force".cyan()
+            );
+            continue;
+        }
+        to_remove.push(installed);
+    }
+
+    if to_remove.is_empty() {
+        report!(
+            "{} No removable Go {} versions are installed.",
+            "Error:".red().bold(),
+            line_version
+        );
+        return Err(Failure::NotInstalled);
+    }
+
+    println!("{}", "To remove:".bold());
+    for version in &to_remove {
+        println!("     {}", extract_version_number(version));
+    }
+    let prompt = format!("Remove {} versions and their SDKs?", to_remove.len());
+    if !confirm_removal(&prompt, yes) {
+        return Err(Failure::Failed);
+    }
+
+    let mut removed = 0;
+    for version in &to_remove {
+        info!(
+            "{} {}",
+            "Removing Go".bold(),
+            extract_version_number(version).green()
+        );
+        if remove_version(version) {
+            removed += 1;
+            if current.as_ref() == Some(version) {
+                save_current_version(None);
+                let _ = fs::remove_file(get_go_link());
             }
         }
-        Err(e) => {
-            eprintln!(
-                "{} Failed to create symlink: {}",
+    }
+
+    info!(
+        "{} Uninstalled {} of {} Go {} versions.",
+        "✓".green().bold(),
+        removed,
+        to_remove.len(),
+        line_version
+    );
+    if removed == to_remove.len() {
+        Ok(())
+    } else {
+        Err(Failure::Failed)
+    }
+}
+
+/// Most per-path removal errors printed before the rest are summarized
+const MAX_REPORTED_REMOVAL_ERRORS: usize = 10;
+
+/// Delete a version's SDK and then its wrapper, returning whether both are
+/// gone. When part of the SDK can't be deleted (e.g. files owned by another
+/// user), the wrapper is kept so the version still shows up and can be
+/// removed again once the problem is fixed.
+fn remove_version(normalized: &str) -> bool {
+    remove_installed(&get_go_wrapper(normalized), &get_sdk_dir().join(normalized))
+}
+
+/// `remove_version` for a given wrapper and SDK directory
+fn remove_installed(go_wrapper: &Path, sdk_dir: &Path) -> bool {
+    if sdk_dir.exists() && !remove_sdk(sdk_dir) {
+        report!(
+            "{}",
+            "The wrapper was left in place; fix the permissions and try again.".yellow()
+        );
+        return false;
+    }
+
+    // Not `exists()`: that follows the link into the SDK just deleted
+    if fs::symlink_metadata(go_wrapper).is_ok() {
+        if let Err(e) = fs::remove_file(go_wrapper) {
+            report!(
+                "{} Failed to remove wrapper {:?}: {}",
                 "Error:".red().bold(),
+                go_wrapper,
                 e
             );
+            return false;
+        }
+        info!("{}", "  ✓ Go wrapper removed".green());
+    }
+
+    true
+}
+
+/// Delete an SDK directory, reporting what couldn't be removed
+fn remove_sdk(sdk_dir: &Path) -> bool {
+    let errors = remove_tree(sdk_dir);
+    if !errors.is_empty() {
+        report!(
+            "{} Failed to remove {} path(s) from SDK {:?}:",
+            "Error:".red().bold(),
+            errors.len(),
+            sdk_dir
+        );
+        for (path, e) in errors.iter().take(MAX_REPORTED_REMOVAL_ERRORS) {
+            report!("  {}: {}", path.display(), e);
+        }
+        if errors.len() > MAX_REPORTED_REMOVAL_ERRORS {
+            report!(
+                "  ...and {} more",
+                errors.len() - MAX_REPORTED_REMOVAL_ERRORS
+            );
+        }
+        return false;
+    }
+    info!("{}", "  ✓ Go SDK removed".green());
+    true
+}
+
+/// Remove a directory tree, carrying on past failures and returning every
+/// path that couldn't be removed. Directories left non-empty by an earlier
+/// failure aren't reported again.
+fn remove_tree(dir: &Path) -> Vec<(PathBuf, io::Error)> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => return vec![(dir.to_path_buf(), e)],
+    };
+
+    let mut errors = Vec::new();
+    for entry in entries {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(e) => {
+                errors.push((dir.to_path_buf(), e));
+                continue;
+            }
+        };
+        // Don't follow symlinks out of the SDK
+        let is_dir = fs::symlink_metadata(&path).is_ok_and(|m| m.is_dir());
+        if is_dir {
+            errors.extend(remove_tree(&path));
+        } else if let Err(e) = fs::remove_file(&path) {
+            errors.push((path, e));
+        }
+    }
+
+    if errors.is_empty() {
+        if let Err(e) = fs::remove_dir(dir) {
+            errors.push((dir.to_path_buf(), e));
+        }
+    }
+    errors
+}
+
+fn cmd_pin(version: &str, pin: bool) -> CmdResult {
+    let version = resolve_alias(version)?;
+    let normalized = normalize_version(&version);
+    let version_num = extract_version_number(&normalized).to_string();
+    let mut config = load_config();
+
+    let is_pinned = config.pinned.contains(&version_num);
+    if pin {
+        if !get_go_wrapper(&normalized).exists() {
+            report!(
+                "{} Go {} is not installed.",
+                "Error:".red().bold(),
+                version_num
+            );
+            return Err(Failure::NotInstalled);
+        }
+        if is_pinned {
+            println!("Go {} is already pinned.", version_num.green());
+            return Ok(());
+        }
+        config.pinned.push(version_num.clone());
+    } else {
+        if !is_pinned {
+            report!(
+                "{} Go {} is not pinned.",
+                "Error:".red().bold(),
+                version_num
+            );
+            return Err(Failure::Failed);
+        }
+        config.pinned.retain(|v| *v != version_num);
+    }
+
+    if let Err(e) = save_config(&config) {
+        report!("{} {}", "Error:".red().bold(), e);
+        return Err(Failure::Failed);
+    }
+    if pin {
+        println!(
+            "{} Pinned Go {}; prune will keep it.",
+            "✓".green().bold(),
+            version_num.green()
+        );
+    } else {
+        println!("{} Unpinned Go {}", "✓".green().bold(), version_num.green());
+    }
+    Ok(())
+}
+
+fn cmd_prune(keep: usize, dry_run: bool, yes: bool) -> CmdResult {
+    // Installed versions come back oldest first
+    let installed = list_installed_versions();
+    let current = get_current_version();
+    let pinned = load_config().pinned;
+
+    let newest = &installed[installed.len().saturating_sub(keep)..];
+    let to_remove: Vec<_> = installed
+        .iter()
+        .filter(|v| !newest.contains(v) && current.as_ref() != Some(*v))
+        .filter(|v| !pinned.iter().any(|p| p == extract_version_number(v)))
+        .collect();
+
+    if to_remove.is_empty() {
+        println!("{}", "Nothing to prune.".dimmed());
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        if dry_run { "Would remove:" } else { "To remove:" }.bold()
+    );
+    for version in &to_remove {
+        println!("     {}", extract_version_number(version));
+    }
+    if dry_run {
+        return Ok(());
+    }
+
+    let prompt = format!("Remove {} versions and their SDKs?", to_remove.len());
+    if !confirm_removal(&prompt, yes) {
+        return Err(Failure::Failed);
+    }
+
+    let mut removed = 0;
+    for version in &to_remove {
+        info!(
+            "{} {}",
+            "Removing Go".bold(),
+            extract_version_number(version).green()
+        );
+        if remove_version(version) {
+            removed += 1;
+        }
+    }
+
+    info!(
+        "{} Pruned {} of {} versions.",
+        "✓".green().bold(),
+        removed,
+        to_remove.len()
+    );
+    if removed == to_remove.len() {
+        Ok(())
+    } else {
+        Err(Failure::Failed)
+    }
+}
+
+/// Give the owner write access to everything under `dir`; the module cache
+/// makes its files and directories read-only, which stops `remove_tree`
+fn make_tree_writable(dir: &Path) {
+    let Ok(meta) = fs::symlink_metadata(dir) else {
+        return;
+    };
+    if !meta.is_dir() {
+        return;
+    }
+    let mut permissions = meta.permissions();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() | 0o700);
+    }
+    #[cfg(windows)]
+    #[allow(clippy::permissions_set_readonly_false)]
+    permissions.set_readonly(false);
+    let _ = fs::set_permissions(dir, permissions);
+
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        make_tree_writable(&entry.path());
+    }
+}
+
+/// `golang.org/dl` in the Go module cache: the extracted `golang.org/dl@v...`
+/// directories and their downloads under `cache/download`
+fn dl_module_cache_paths() -> Option<Vec<PathBuf>> {
+    let go = find_on_path("go")?;
+    let mut command = Command::new(go);
+    command.args(["env", "GOMODCACHE"]);
+    log_command(&command);
+    let output = command.output().ok().filter(|o| o.status.success())?;
+    let mod_cache = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    if mod_cache.as_os_str().is_empty() {
+        return None;
+    }
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(mod_cache.join("golang.org"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("dl@"))
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    let downloads = mod_cache.join("cache").join("download").join("golang.org").join("dl");
+    if downloads.exists() {
+        paths.push(downloads);
+    }
+    Some(paths)
+}
+
+/// Host SDKs in the SDK directory with no wrapper left to use them.
+/// SDKs downloaded for other platforms never have one and are kept.
+fn orphaned_sdks() -> Vec<String> {
+    let mut orphans: Vec<String> = fs::read_dir(get_sdk_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| wrapper_version(name).is_some() && !get_go_wrapper(name).exists())
+        .collect();
+    orphans.sort_by(|a, b| version_order(extract_version_number(a), extract_version_number(b)));
+    orphans
+}
+
+/// Remove `path` (a file or a directory tree), returning the space freed
+fn remove_cached(path: &Path, label: &str) -> Option<u64> {
+    let size = if path.is_dir() {
+        dir_size(path)
+    } else {
+        fs::metadata(path).map_or(0, |m| m.len())
+    };
+    let result = if path.is_dir() {
+        make_tree_writable(path);
+        match remove_tree(path).into_iter().next() {
+            Some((failed, e)) => Err(format!("{}: {}", failed.display(), e)),
+            None => Ok(()),
+        }
+    } else {
+        fs::remove_file(path).map_err(|e| e.to_string())
+    };
+
+    match result {
+        Ok(()) => {
+            info!("  {} Removed {} ({})", "✓".green(), label, format_size(size));
+            Some(size)
+        }
+        Err(e) => {
+            report!("{} Failed to remove {}: {}", "Error:".red().bold(), label, e);
+            None
+        }
+    }
+}
+
+/// Whether two paths name the same directory, allowing for symlinks and
+/// `.` components the way `is_on_path` does
+fn same_dir(a: &Path, b: &Path) -> bool {
+    a == b || fs::canonicalize(a).is_ok_and(|a| fs::canonicalize(b).is_ok_and(|b| a == b))
+}
+
+/// Whether `go_on_path`, the first `go` on PATH, is something other than gvm's
+/// link while that link exists under the same name, so it never gets run
+fn shadows_go_link(go_on_path: &Path, go_link: &Path) -> bool {
+    fs::symlink_metadata(go_link).is_ok()
+        && go_on_path.file_name() == go_link.file_name()
+        && !go_on_path
+            .parent()
+            .zip(go_link.parent())
+            .is_some_and(|(found, link)| same_dir(found, link))
+}
+
+/// Best guess at what installed a `go` found on PATH, from where it lives
+fn go_manager(path: &Path) -> &'static str {
+    let path = path.to_string_lossy();
+    [
+        (".asdf", "asdf"),
+        (".goenv", "goenv"),
+        ("mise", "mise"),
+        (".gvm", "moovweb/gvm"),
+        ("/snap/", "snap"),
+        ("homebrew", "Homebrew"),
+        ("Cellar", "Homebrew"),
+        ("/usr/local/go/", "the official installer"),
+    ]
+    .into_iter()
+    .find(|(marker, _)| path.contains(marker))
+    .map_or("another install", |(_, manager)| manager)
+}
+
+fn cmd_rename_link(name: &str) -> CmdResult {
+    if std::env::var_os("GVM_LINK_NAME").is_some_and(|v| !v.is_empty()) {
+        report!(
+            "{} GVM_LINK_NAME is set and takes precedence; change it instead.",
+            "Error:".red().bold()
+        );
+        return Err(Failure::Failed);
+    }
+    let valid = !name.is_empty()
+        && !name.contains(std::path::is_separator)
+        && !name.starts_with('.')
+        && wrapper_version(name).is_none();
+    if !valid {
+        report!(
+            "{} {:?} can't be used as the link name.",
+            "Error:".red().bold(),
+            name
+        );
+        report!("Pick a file name that isn't a goX.Y.Z wrapper, e.g. gvm-go.");
+        return Err(Failure::Failed);
+    }
+
+    let old_link = get_go_link();
+    let new_link = get_link_dir().join(format!("{}{}", name, EXE_SUFFIX));
+    if new_link != old_link {
+        if fs::symlink_metadata(&new_link).is_ok() {
+            report!(
+                "{} {:?} already exists; remove it first.",
+                "Error:".red().bold(),
+                new_link
+            );
+            return Err(Failure::Failed);
+        }
+        if fs::symlink_metadata(&old_link).is_ok() {
+            if let Err(e) = fs::rename(&old_link, &new_link) {
+                report!(
+                    "{} Failed to rename {:?}: {}",
+                    "Error:".red().bold(),
+                    old_link,
+                    e
+                );
+                return Err(Failure::Failed);
+            }
+        }
+    }
+
+    let mut config = load_config();
+    config.link_name = (name != "go").then(|| name.to_string());
+    if let Err(e) = save_config(&config) {
+        report!("{} {}", "Error:".red().bold(), e);
+        return Err(Failure::Failed);
+    }
+
+    println!(
+        "{} gvm's link is now {}",
+        "✓".green().bold(),
+        new_link.display().to_string().green()
+    );
+    Ok(())
+}
+
+fn cmd_clean(modules: bool, sdks: bool, yes: bool) -> CmdResult {
+    let mut to_remove: Vec<(PathBuf, String)> = Vec::new();
+
+    if let Some(cache) = get_release_cache_file().filter(|path| path.exists()) {
+        to_remove.push((cache, "the release list cache".to_string()));
+    }
+    if modules {
+        match dl_module_cache_paths() {
+            Some(paths) => to_remove.extend(paths.into_iter().map(|path| {
+                let label = format!("{} from the module cache", path.display());
+                (path, label)
+            })),
+            None => report!(
+                "{} Couldn't find the Go module cache (no go on PATH to ask); skipping it.",
+                "Warning:".yellow().bold()
+            ),
+        }
+    }
+
+    if sdks {
+        let orphans = orphaned_sdks();
+        if !orphans.is_empty() {
+            println!("{}", "SDKs without a wrapper:".bold());
+            for sdk in &orphans {
+                println!("     {}", extract_version_number(sdk));
+            }
+            let prompt = format!("Remove {} orphaned SDKs?", orphans.len());
+            if !confirm_removal(&prompt, yes) {
+                return Err(Failure::Failed);
+            }
+            to_remove.extend(orphans.into_iter().map(|sdk| {
+                let label = format!("the Go {} SDK", extract_version_number(&sdk));
+                (get_sdk_dir().join(sdk), label)
+            }));
+        }
+    }
+
+    if to_remove.is_empty() {
+        println!("{}", "Nothing to clean.".dimmed());
+        return Ok(());
+    }
+
+    let mut reclaimed = 0;
+    let mut failed = false;
+    for (path, label) in &to_remove {
+        match remove_cached(path, label) {
+            Some(size) => reclaimed += size,
+            None => failed = true,
         }
     }
+
+    println!(
+        "{} Reclaimed {}.",
+        "✓".green().bold(),
+        format_size(reclaimed)
+    );
+    if failed {
+        Err(Failure::Failed)
+    } else {
+        Ok(())
+    }
+}
+
+fn cmd_history(json: bool, limit: usize) -> CmdResult {
+    let history = load_history();
+    let recent = &history[history.len().saturating_sub(limit)..];
+
+    if json {
+        print_json(&recent);
+        return Ok(());
+    }
+
+    if recent.is_empty() {
+        println!("{}", "No version switches recorded yet.".yellow());
+        return Ok(());
+    }
+
+    println!("{}", "Recent version switches:".bold());
+    for entry in recent.iter().rev() {
+        let previous = entry
+            .previous
+            .as_ref()
+            .map(|p| format!(" (was {})", p).dimmed().to_string())
+            .unwrap_or_default();
+        println!(
+            "  {}  {:<8} {}{}",
+            format_timestamp(entry.timestamp).dimmed(),
+            entry.command,
+            entry.version.green(),
+            previous
+        );
+    }
+    Ok(())
 }
 
-fn main() {
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    if cli.json || cli.no_color || no_color_env || !io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+    QUIET.store(cli.quiet, Ordering::Relaxed);
+    JSON_REPORTS.store(cli.json, Ordering::Relaxed);
+
+    if let Err(e) = paths::init() {
+        fatal(&e);
+    }
+    if let Some(config) = cli.config.clone() {
+        set_config_file(config);
+    }
+    // Commands that need the rest of the config report a broken one
+    // themselves; the others just keep the default link name
+    match try_load_config() {
+        Ok(config) => {
+            if let Some(name) = config.link_name {
+                set_link_name(name);
+            }
+        }
+        Err(e) => report!("{} {}", "Warning:".yellow().bold(), e),
+    }
+
+    if cli.verbose {
+        VERBOSE.store(true, Ordering::Relaxed);
+        log_verbose(&format!("bin directory: {}", get_go_bin_dir().display()));
+        log_verbose(&format!("go link: {}", get_go_link().display()));
+        log_verbose(&format!("SDK directory: {}", get_sdk_dir().display()));
+        log_verbose(&format!("config file: {}", get_config_file().display()));
+    }
+
+    let _ = NETWORK_OPTIONS.set(NetworkOptions {
+        base_url: cli.base_url.clone(),
+        proxy: cli.proxy.clone(),
+    });
+
+    let result = match cli.command {
+        Commands::List(args) => cmd_list(cli.json, &args),
+        Commands::ListAll(args) => cmd_list_all(cli.json, &args),
+        Commands::Install {
+            from_file: Some(archive),
+            file_version,
+            mut options,
+            ..
+        } => {
+            options.quiet = cli.quiet;
+            let options = options.with_defaults();
+            cmd_install_from_file(&archive, file_version.as_deref(), &options)
+        }
+        Commands::Install {
+            versions,
+            checksum_only: true,
+            ..
+        } => cmd_verify(&versions),
+        Commands::Install {
+            versions,
+            latest,
+            include_unstable,
+            use_after,
+            mut options,
+            ..
+        } => {
+            options.quiet = cli.quiet;
+            let options = options.with_defaults();
+            let versions = if latest {
+                vec!["latest".to_string()]
+            } else {
+                versions
+            };
+            // Resolved up front so --use switches to the same release
+            let versions = if include_unstable {
+                versions
+                    .into_iter()
+                    .map(|v| if v == "latest" { newest_release() } else { Ok(v) })
+                    .collect::<Result<Vec<_>, _>>()
+            } else {
+                Ok(versions)
+            };
+            versions.and_then(|versions| {
+                cmd_install_many(&versions, &options).and_then(|_| match versions.last() {
+                    Some(version) if use_after && !options.dry_run => {
+                        info!();
+                        cmd_use(Some(version), false, false, &[], &[])
+                    }
+                    _ => Ok(()),
+                })
+            })
+        }
+        Commands::Reinstall { version } => cmd_reinstall(&version, cli.quiet),
+        Commands::Use {
+            version,
+            from_gomod,
+            shell,
+            set_env,
+            unset_env,
+        } => cmd_use(version.as_deref(), from_gomod, shell, &set_env, &unset_env),
+        Commands::Alias {
+            name,
+            version,
+            delete,
+        } => cmd_alias(name.as_deref(), version.as_deref(), delete),
+        Commands::Default { version } => cmd_default(version.as_deref()),
+        Commands::Env { shell } => cmd_env(shell.unwrap_or_else(EnvShell::detect)),
+        Commands::Hook { shell } => cmd_hook(shell),
+        Commands::Completions { shell } => cmd_completions(shell),
+        Commands::CompleteVersions => cmd_complete_versions(),
+        Commands::Which { version, goroot } => cmd_which(&version, goroot),
+        Commands::Run { version, args } => cmd_run(&version, &args),
+        Commands::Exec { command } => cmd_exec(&command),
+        Commands::Current => cmd_current(cli.json),
+        Commands::Status => cmd_status(cli.json),
+        Commands::Uninstall {
+            minor: Some(line),
+            force,
+            yes,
+            ..
+        } => cmd_uninstall_minor(&line, force, yes),
+        Commands::Uninstall {
+            version,
+            force,
+            yes,
+            os,
+            arch,
+            ..
+        } => {
+            let version = version.as_deref().unwrap_or_default();
+            let os = os.as_deref().unwrap_or(host_go_os());
+            let arch = arch.as_deref().unwrap_or(host_archive_arch());
+            if os == host_go_os() && arch == host_archive_arch() {
+                cmd_uninstall(version, force, yes)
+            } else {
+                cmd_uninstall_platform(version, os, arch, yes)
+            }
+        }
+        Commands::SelfUpdate { check_only } => cmd_self_update(check_only),
+        Commands::Doctor => cmd_doctor(),
+        Commands::Verify { version, .. } => cmd_verify(&Vec::from_iter(version)),
+        Commands::Pin { version } => cmd_pin(&version, true),
+        Commands::Unpin { version } => cmd_pin(&version, false),
+        Commands::Prune { keep, dry_run, yes } => cmd_prune(keep, dry_run, yes),
+        Commands::RenameLink { name } => cmd_rename_link(&name),
+        Commands::Clean { modules, all, yes } => cmd_clean(modules || all, all, yes),
+        Commands::History { limit } => cmd_history(cli.json, limit),
+    };
+
+    flush_json_reports(result.as_ref().map(|_| ()));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => failure.exit_code(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_command_aliases_parse() {
+        let parse = |args: &[&str]| Cli::try_parse_from(args).map(|cli| cli.command);
+        assert!(matches!(parse(&["gvm", "ls"]), Ok(Commands::List(_))));
+        assert!(matches!(parse(&["gvm", "ls-remote"]), Ok(Commands::ListAll(_))));
+        assert!(matches!(
+            parse(&["gvm", "i", "1.22"]),
+            Ok(Commands::Install { versions, .. }) if versions == ["1.22"]
+        ));
+    }
+
+    #[test]
+    fn command_line_install_options_override_defaults() {
+        let defaults =
+            DefaultInstallFlags::try_parse_from(["--timeout", "600", "--dry-run", "--os", "darwin"])
+                .unwrap();
+        let given = InstallArgs {
+            timeout: Some(5),
+            ..InstallArgs::default()
+        };
+        let merged = given.merge(&defaults.options);
+        assert_eq!(merged.timeout, Some(5));
+        assert!(merged.dry_run);
+        assert_eq!(merged.os.as_deref(), Some("darwin"));
+        assert!(merged.check_conflicts().is_ok());
+        assert!(DefaultInstallFlags::try_parse_from(["--bogus"]).is_err());
+
+        let given = InstallArgs {
+            no_dry_run: true,
+            only_wrapper: true,
+            ..InstallArgs::default()
+        };
+        let merged = given.merge(&defaults.options);
+        assert!(!merged.dry_run);
+        assert!(merged.check_conflicts().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn uninstall_removes_a_wrapper_linked_into_its_sdk() {
+        let dir = std::env::temp_dir().join(format!("gvm-uninstall-test-{}", std::process::id()));
+        let sdk_dir = dir.join("sdk").join("go1.20.3");
+        let go_wrapper = dir.join("bin").join("go1.20.3");
+        fs::create_dir_all(sdk_dir.join("bin")).unwrap();
+        fs::create_dir_all(go_wrapper.parent().unwrap()).unwrap();
+        fs::write(sdk_dir.join("bin").join("go"), "").unwrap();
+        switch_go_link(&sdk_dir.join("bin").join("go"), &go_wrapper).unwrap();
+
+        assert!(remove_installed(&go_wrapper, &sdk_dir));
+        let gone = fs::symlink_metadata(&go_wrapper).is_err() && !sdk_dir.exists();
+        let _ = fs::remove_dir_all(&dir);
+        assert!(gone);
+    }
+
+    #[test]
+    fn env_assignments_need_a_plain_variable_name() {
+        assert_eq!(
+            parse_env_assignment("GOEXPERIMENT=rangefunc"),
+            Ok(("GOEXPERIMENT".to_string(), "rangefunc".to_string()))
+        );
+        assert_eq!(parse_env_assignment("_A1=x=y"), Ok(("_A1".to_string(), "x=y".to_string())));
+        for bad in ["NOVALUE", "=x", "1A=x", "A B=x", "A;rm -rf ~=x", "A-B=x"] {
+            assert!(parse_env_assignment(bad).is_err(), "{} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn shell_quote_keeps_values_literal() {
+        let value = r#"a'b"$c`d`;\e"#;
+        assert_eq!(shell_quote(EnvShell::Bash, value), r#"'a'\''b"$c`d`;\e'"#);
+        assert_eq!(shell_quote(EnvShell::Fish, value), r#"'a\'b"$c`d`;\\e'"#);
+        assert_eq!(shell_quote(EnvShell::Powershell, value), r#"'a''b"$c`d`;\e'"#);
+    }
+
+    #[test]
+    fn go_link_is_shadowed_only_when_it_exists() {
+        let root = std::env::temp_dir().join(format!("gvm-shadow-test-{}", std::process::id()));
+        let link_dir = root.join("bin");
+        let other_dir = root.join("other");
+        fs::create_dir_all(&link_dir).unwrap();
+        fs::create_dir_all(&other_dir).unwrap();
+        let go_link = link_dir.join(format!("go{}", EXE_SUFFIX));
+        let other_go = other_dir.join(format!("go{}", EXE_SUFFIX));
+        fs::write(&other_go, "").unwrap();
+
+        // After `gvm use system` there's no link to shadow
+        assert!(!shadows_go_link(&other_go, &go_link));
+
+        fs::write(&go_link, "").unwrap();
+        assert!(shadows_go_link(&other_go, &go_link));
+        assert!(!shadows_go_link(&go_link, &go_link));
+        let same_link = link_dir.join(".").join(format!("go{}", EXE_SUFFIX));
+        assert!(!shadows_go_link(&same_link, &go_link));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn available_versions_lists_each_version_once() {
+        let releases: Vec<GoRelease> = serde_json::from_str(
+            r#"[
+                {"version": "go1.22.1", "stable": true},
+                {"version": "go1.23rc1", "stable": false},
+                {"version": "go1.21.13", "stable": true},
+                {"version": "go1.22.1", "stable": true},
+                {"version": "go1.23rc1", "stable": false},
+                {"version": "go1.22.1", "stable": true}
+            ]"#,
+        )
+        .unwrap();
+
+        let versions: Vec<_> = available_versions(&releases, false)
+            .into_iter()
+            .map(|(version, _)| version)
+            .collect();
+        assert_eq!(versions, ["1.23rc1", "1.22.1", "1.21.13"]);
 
-    match cli.command {
-        Commands::List => cmd_list(),
-        Commands::ListAll => cmd_list_all(),
-        Commands::Install { version } => cmd_install(&version),
-        Commands::Use { version } => cmd_use(&version),
+        let stable: Vec<_> = available_versions(&releases, true)
+            .into_iter()
+            .map(|(version, _)| version)
+            .collect();
+        assert_eq!(stable, ["1.22.1", "1.21.13"]);
     }
 }
@@ -0,0 +1,125 @@
+//! HTTP access to go.dev and GitHub. Builds without the default `network`
+//! feature leave reqwest out entirely, and every request fails with
+//! [`NO_NETWORK`] instead.
+
+use std::path::Path;
+
+/// Why a request failed in a build without the `network` feature
+#[cfg(not(feature = "network"))]
+pub const NO_NETWORK: &str = "gvm was built without network support";
+
+#[cfg(feature = "network")]
+use std::{fs, io, time::Duration};
+
+#[cfg(feature = "network")]
+use crate::network_options;
+
+#[cfg(feature = "network")]
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum time to wait on any single read from go.dev
+#[cfg(feature = "network")]
+const HTTP_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Extra attempts made after a failed request, backing off 2s then 4s
+#[cfg(feature = "network")]
+const HTTP_RETRIES: u32 = 2;
+
+/// HTTP client for go.dev. Without `--proxy`, reqwest picks up
+/// HTTP_PROXY/HTTPS_PROXY/ALL_PROXY and honors NO_PROXY from the environment.
+#[cfg(feature = "network")]
+fn http_client() -> Result<reqwest::blocking::Client, String> {
+    // GitHub's API rejects requests without a User-Agent
+    let mut builder = reqwest::blocking::Client::builder()
+        .user_agent(concat!("gvm/", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(HTTP_CONNECT_TIMEOUT)
+        .timeout(HTTP_READ_TIMEOUT);
+
+    if let Some(proxy) = &network_options().proxy {
+        let proxy = reqwest::Proxy::all(proxy)
+            .map_err(|e| format!("Invalid proxy URL {}: {}", proxy, e))?;
+        builder = builder.proxy(proxy);
+    }
+
+    builder
+        .build()
+        .map_err(|e| format!("Failed to set up HTTP client: {}", e))
+}
+
+/// Describe a failed request concisely instead of reqwest's nested error chain
+#[cfg(feature = "network")]
+fn describe_http_error(url: &str, e: &reqwest::Error) -> String {
+    let host = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.to_string());
+
+    if e.is_timeout() {
+        format!("timed out contacting {}", host)
+    } else if e.is_connect() {
+        format!("could not connect to {}", host)
+    } else {
+        format!("request to {} failed: {}", host, e)
+    }
+}
+
+/// GET a URL, retrying connection problems, timeouts and server errors with backoff
+#[cfg(feature = "network")]
+fn http_get(url: &str) -> Result<reqwest::blocking::Response, String> {
+    let client = http_client()?;
+    let mut attempt = 0;
+
+    loop {
+        let retryable = match client.get(url).send() {
+            Ok(resp) if resp.status().is_server_error() => format!("HTTP {}", resp.status()),
+            Ok(resp) if !resp.status().is_success() => {
+                return Err(format!("HTTP {}", resp.status()));
+            }
+            Ok(resp) => return Ok(resp),
+            Err(e) if e.is_timeout() || e.is_connect() || e.is_request() => {
+                describe_http_error(url, &e)
+            }
+            Err(e) => return Err(describe_http_error(url, &e)),
+        };
+
+        if attempt >= HTTP_RETRIES {
+            return Err(retryable);
+        }
+        attempt += 1;
+        std::thread::sleep(Duration::from_secs(1 << attempt));
+    }
+}
+
+/// GET a URL and return its body as text
+#[cfg(feature = "network")]
+pub fn get_text(url: &str) -> Result<String, String> {
+    http_get(url)?.text().map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "network"))]
+pub fn get_text(_url: &str) -> Result<String, String> {
+    Err(NO_NETWORK.to_string())
+}
+
+#[cfg(feature = "network")]
+pub fn download_file(url: &str, dest: &Path) -> Result<(), String> {
+    let mut resp = http_get(url).map_err(|e| format!("Failed to download {}: {}", url, e))?;
+
+    let mut file =
+        fs::File::create(dest).map_err(|e| format!("Failed to create {:?}: {}", dest, e))?;
+    io::copy(&mut resp, &mut file).map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "network"))]
+pub fn download_file(url: &str, _dest: &Path) -> Result<(), String> {
+    Err(format!("Failed to download {}: {}", url, NO_NETWORK))
+}
+
+/// Whether a HEAD request to `url` gets any response at all
+#[cfg(feature = "network")]
+pub fn is_reachable(url: &str) -> bool {
+    http_client()
+        .and_then(|client| client.head(url).send().map_err(|e| e.to_string()))
+        .is_ok()
+}
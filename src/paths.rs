@@ -0,0 +1,143 @@
+//! Where gvm keeps things. Go itself is managed in its conventional places
+//! (wrappers in `~/go/bin`, SDKs in `~/sdk`) so plain `goX.X.X` wrappers keep
+//! working; gvm's own state lives in the platform config and cache
+//! directories.
+
+use std::env::consts::EXE_SUFFIX;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::{host_archive_arch, host_go_os};
+
+/// The home and config directories, looked up once by `init`
+static DIRS: OnceLock<(PathBuf, PathBuf)> = OnceLock::new();
+
+/// Find the home and gvm config directories every other path here is built
+/// from. Call this before the rest of the crate: it's the only place that
+/// can fail, when the platform has no home or config directory.
+pub fn init() -> Result<(), String> {
+    let home = dirs::home_dir().ok_or("Could not determine home directory (is $HOME set?)")?;
+    let config = dirs::config_dir().ok_or("Could not determine config directory")?;
+    let _ = DIRS.set((home, config.join("gvm")));
+    Ok(())
+}
+
+/// Looked up on first use when `init` wasn't called, leaving the path
+/// relative if there's nothing to find
+fn get_dirs() -> &'static (PathBuf, PathBuf) {
+    DIRS.get_or_init(|| {
+        let home = dirs::home_dir().unwrap_or_default();
+        let config = dirs::config_dir().unwrap_or_else(|| home.join(".config"));
+        (home, config.join("gvm"))
+    })
+}
+
+fn get_home_dir() -> PathBuf {
+    get_dirs().0.clone()
+}
+
+/// Directory holding the `goX.X.X` wrappers and the `go` symlink:
+/// `$GVM_HOME/bin` when set, otherwise `~/go/bin`
+pub fn get_go_bin_dir() -> PathBuf {
+    match std::env::var_os("GVM_HOME") {
+        Some(gvm_home) if !gvm_home.is_empty() => PathBuf::from(gvm_home).join("bin"),
+        _ => get_home_dir().join("go").join("bin"),
+    }
+}
+
+/// Path to the `goX.X.X` wrapper for a normalized version (`go1.22.11.exe` on Windows)
+pub fn get_go_wrapper(normalized: &str) -> PathBuf {
+    get_go_bin_dir().join(format!("{}{}", normalized, EXE_SUFFIX))
+}
+
+/// Directory holding the managed `go` link: `$GVM_LINK_DIR` when set (e.g.
+/// `~/.local/bin`, to avoid putting the bin directory on PATH), otherwise the
+/// bin directory
+pub fn get_link_dir() -> PathBuf {
+    match std::env::var_os("GVM_LINK_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => get_go_bin_dir(),
+    }
+}
+
+/// Set from the config's `link_name` (see `gvm rename-link`)
+static LINK_NAME: OnceLock<String> = OnceLock::new();
+
+/// Use `name` for the managed link for the rest of this run, unless
+/// `$GVM_LINK_NAME` says otherwise
+pub fn set_link_name(name: String) {
+    let _ = LINK_NAME.set(name);
+}
+
+/// Name of the managed link: `$GVM_LINK_NAME`, then the configured name, then
+/// `go`. Another name keeps gvm from competing with asdf or goenv shims.
+pub fn get_link_name() -> String {
+    match std::env::var("GVM_LINK_NAME") {
+        Ok(name) if !name.is_empty() => name,
+        _ => LINK_NAME.get().cloned().unwrap_or_else(|| "go".to_string()),
+    }
+}
+
+/// Path to the managed `go` binary that points at the active wrapper
+pub fn get_go_link() -> PathBuf {
+    get_link_dir().join(format!("{}{}", get_link_name(), EXE_SUFFIX))
+}
+
+pub fn get_sdk_dir() -> PathBuf {
+    get_home_dir().join("sdk")
+}
+
+/// SDK root for a version. SDKs for the host live in `~/sdk/goX.X.X` as
+/// `goX.X.X download` lays them out; cross-downloaded ones are namespaced as
+/// `~/sdk/goX.X.X.<os>-<arch>` so several platforms can coexist.
+pub fn get_sdk_root(normalized: &str, os: &str, arch: &str) -> PathBuf {
+    if os == host_go_os() && arch == host_archive_arch() {
+        get_sdk_dir().join(normalized)
+    } else {
+        get_sdk_dir().join(format!("{}.{}-{}", normalized, os, arch))
+    }
+}
+
+/// gvm's own cache (`$XDG_CACHE_HOME/gvm` on Linux), if the platform has one
+fn get_cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("gvm"))
+}
+
+pub fn get_release_cache_file() -> Option<PathBuf> {
+    get_cache_dir().map(|dir| dir.join("releases.json"))
+}
+
+/// Records when `gvm list` last tried to refresh the release list itself
+pub fn get_update_check_file() -> Option<PathBuf> {
+    get_cache_dir().map(|dir| dir.join("update-check"))
+}
+
+/// gvm's own configuration (`$XDG_CONFIG_HOME/gvm` on Linux)
+fn get_config_dir() -> PathBuf {
+    get_dirs().1.clone()
+}
+
+/// Set by `--config`
+static CONFIG_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `path` as the config file for the rest of this run
+pub fn set_config_file(path: PathBuf) {
+    let _ = CONFIG_FILE.set(path);
+}
+
+/// `--config`, then `$GVM_CONFIG`, then `config.toml` in the config directory
+pub fn get_config_file() -> PathBuf {
+    if let Some(path) = CONFIG_FILE.get() {
+        return path.clone();
+    }
+    match std::env::var_os("GVM_CONFIG") {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => get_config_dir().join("config.toml"),
+    }
+}
+
+/// Log of `gvm use` and `gvm default` switches, shown by `gvm history`. It
+/// sits next to the config file, so a custom config gets its own history.
+pub fn get_history_file() -> PathBuf {
+    get_config_file().with_file_name("history.log")
+}
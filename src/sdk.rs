@@ -0,0 +1,105 @@
+//! Installed SDKs and the links that select one: what's in the bin
+//! directory, switching the `go` link, and the commands that install a
+//! version
+
+use std::env::consts::EXE_SUFFIX;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use crate::paths::{get_go_bin_dir, get_sdk_root};
+use crate::version::{extract_version_number, version_order, wrapper_version};
+use crate::{host_archive_arch, host_go_os, host_goarch_env};
+
+/// Installed versions (`go1.22.5`, ...) found as wrappers in the bin
+/// directory, oldest first
+pub fn list_installed_versions() -> io::Result<Vec<String>> {
+    let bin_dir = get_go_bin_dir();
+
+    if !bin_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut versions: Vec<String> = fs::read_dir(&bin_dir)?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let file_name = file_name.strip_suffix(EXE_SUFFIX).unwrap_or(&file_name);
+            wrapper_version(file_name).map(|_| file_name.to_string())
+        })
+        .collect();
+
+    versions.sort_by(|a, b| {
+        let a_ver = extract_version_number(a);
+        let b_ver = extract_version_number(b);
+        version_order(a_ver, b_ver)
+    });
+
+    Ok(versions)
+}
+
+/// Point `link` at the `target` wrapper
+#[cfg(unix)]
+pub fn link_go_binary(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+/// Point `link` at the `target` wrapper.
+/// Creating symlinks on Windows requires Developer Mode or admin rights,
+/// so fall back to copying the wrapper when that is not permitted.
+#[cfg(windows)]
+pub fn link_go_binary(target: &Path, link: &Path) -> io::Result<()> {
+    // ERROR_PRIVILEGE_NOT_HELD
+    const PRIVILEGE_NOT_HELD: i32 = 1314;
+
+    match std::os::windows::fs::symlink_file(target, link) {
+        Err(e)
+            if e.kind() == io::ErrorKind::PermissionDenied
+                || e.raw_os_error() == Some(PRIVILEGE_NOT_HELD) =>
+        {
+            fs::copy(target, link).map(|_| ())
+        }
+        result => result,
+    }
+}
+
+/// Point the `go` symlink (or a wrapper linked into its SDK) at `target`
+/// without a moment where it's missing: the new link is made under a
+/// temporary name and renamed over the old one
+pub fn switch_go_link(target: &Path, go_link: &Path) -> io::Result<()> {
+    if let Some(dir) = go_link.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp_link = go_link.with_file_name(format!(".go-switch{}", EXE_SUFFIX));
+    let _ = fs::remove_file(&tmp_link);
+
+    link_go_binary(target, &tmp_link)?;
+    fs::rename(&tmp_link, go_link).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp_link);
+    })
+}
+
+/// `go install golang.org/dl/goX.X.X@latest` run with `bootstrap`.
+/// GOBIN makes `go install` drop the wrapper in gvm's bin dir even when
+/// GOPATH or GVM_HOME point somewhere other than ~/go. GOOS/GOARCH left
+/// exported for cross-compiling would otherwise leak into the wrapper.
+pub fn wrapper_install_command(bootstrap: &Path, normalized: &str) -> Command {
+    let mut command = Command::new(bootstrap);
+    command
+        .args(["install", &format!("golang.org/dl/{}@latest", normalized)])
+        .env("GOBIN", get_go_bin_dir())
+        .env("GOOS", host_go_os())
+        .env("GOARCH", host_goarch_env());
+    command
+}
+
+/// Whether a host SDK directory was left behind by an interrupted download:
+/// it exists but lacks `bin/go` or the `.unpacked-success` marker that
+/// `goX.X.X download` writes once unpacking has finished
+pub fn is_partial_sdk(normalized: &str) -> bool {
+    let sdk_root = get_sdk_root(normalized, host_go_os(), host_archive_arch());
+    sdk_root.is_dir()
+        && !(sdk_root.join("bin").join(format!("go{}", EXE_SUFFIX)).is_file()
+            && sdk_root.join(".unpacked-success").exists())
+}
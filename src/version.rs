@@ -0,0 +1,257 @@
+//! Go version names: parsing, normalizing and ordering them the way Go
+//! orders its releases
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// `go1.22.5` from `1.22.5`; names that already have the prefix are kept as is
+pub fn normalize_version(version: &str) -> String {
+    if version.starts_with("go") {
+        version.to_string()
+    } else {
+        format!("go{}", version)
+    }
+}
+
+/// `1.22.5` from `go1.22.5`; anything without the prefix is returned as is,
+/// so it also accepts what users type. Use `wrapper_version` for names that
+/// may not be versions at all.
+pub fn extract_version_number(version: &str) -> &str {
+    version.strip_prefix("go").unwrap_or(version)
+}
+
+/// The version a file name like `go1.22.5` stands for, or `None` when it's
+/// some other `go`-prefixed file (`go`, `golint`, `go1.22.5.bak`)
+pub fn wrapper_version(file_name: &str) -> Option<&str> {
+    let version = file_name.strip_prefix("go")?;
+    version.parse::<Version>().is_ok().then_some(version)
+}
+
+/// Pre-release stage of a Go version, ordered as Go orders them:
+/// `1.23beta1 < 1.23rc1 < 1.23.0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PreRelease {
+    Beta(u32),
+    Rc(u32),
+    Final,
+}
+
+/// A Go version such as `1.22.11`, `1.20` or `1.23rc1`, with or without the
+/// `go` prefix
+#[derive(Debug, Clone, Copy)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    /// Missing for pre-releases and for the first release of a line before
+    /// Go 1.21, which was named `1.20` rather than `1.20.0`
+    pub patch: Option<u32>,
+    pub pre: PreRelease,
+}
+
+impl Version {
+    /// Whether this names a whole minor line (`1.22`) rather than a patch
+    pub fn is_minor_only(&self) -> bool {
+        self.patch.is_none() && self.pre == PreRelease::Final
+    }
+
+    /// Whether this is a release (not a pre-release) of `line`'s minor line,
+    /// e.g. `1.22.5` or `1.22` in `1.22`
+    pub fn in_minor_line(&self, line: &Version) -> bool {
+        self.major == line.major && self.minor == line.minor && self.pre == PreRelease::Final
+    }
+
+    /// `1.20` and `1.20.0` name the same release
+    fn sort_key(&self) -> (u32, u32, u32, PreRelease) {
+        (self.major, self.minor, self.patch.unwrap_or(0), self.pre)
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let version = extract_version_number(s);
+        let invalid = || format!("{} is not a valid Go version", version);
+        let number = |s: &str| -> Option<u32> {
+            if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            s.parse().ok()
+        };
+
+        // Split "1.23rc1" into "1.23" and "rc1"
+        let suffix_start = version
+            .find(|c: char| c.is_ascii_alphabetic())
+            .unwrap_or(version.len());
+        let (numbers, suffix) = version.split_at(suffix_start);
+
+        let pre = if suffix.is_empty() {
+            PreRelease::Final
+        } else if let Some(n) = suffix.strip_prefix("beta").and_then(number) {
+            PreRelease::Beta(n)
+        } else if let Some(n) = suffix.strip_prefix("rc").and_then(number) {
+            PreRelease::Rc(n)
+        } else {
+            return Err(invalid());
+        };
+
+        let parts: Vec<u32> = numbers
+            .split('.')
+            .map(number)
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
+
+        // Pre-releases come before the first patch: 1.23rc1 but not 1.23.1rc1
+        match (parts.as_slice(), pre) {
+            ([major, minor], _) => Ok(Version {
+                major: *major,
+                minor: *minor,
+                patch: None,
+                pre,
+            }),
+            ([major, minor, patch], PreRelease::Final) => Ok(Version {
+                major: *major,
+                minor: *minor,
+                patch: Some(*patch),
+                pre,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        if let Some(patch) = self.patch {
+            write!(f, ".{}", patch)?;
+        }
+        match self.pre {
+            PreRelease::Beta(n) => write!(f, "beta{}", n),
+            PreRelease::Rc(n) => write!(f, "rc{}", n),
+            PreRelease::Final => Ok(()),
+        }
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.sort_key() == other.sort_key()
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+/// Compare two version strings; ones that don't parse sort first
+pub fn version_compare(a: &str, b: &str) -> Ordering {
+    a.parse::<Version>().ok().cmp(&b.parse::<Version>().ok())
+}
+
+/// `version_compare` made a total order for sorting: names of the same
+/// release (`1.20` and `1.20.0`) and strings that don't parse fall back to
+/// comparing the text, so lists come out the same whatever order go.dev or
+/// the file system returned them in
+pub fn version_order(a: &str, b: &str) -> Ordering {
+    version_compare(a, b).then_with(|| a.cmp(b))
+}
+
+/// The newest of `candidates` (`go1.22.5`, ...) that a partial version like
+/// `1.22` (any `1.22.x`) or `1` (any `1.x.y`) names. Releases win over
+/// pre-releases, which are only picked when no release matches.
+pub fn best_partial_match<'a>(
+    partial: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let parts: Vec<u32> = extract_version_number(partial)
+        .split('.')
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    let (major, minor) = match parts.as_slice() {
+        [major] => (*major, None),
+        [major, minor] => (*major, Some(*minor)),
+        _ => return None,
+    };
+
+    candidates
+        .into_iter()
+        .filter_map(|v| Some((v.parse::<Version>().ok()?, v)))
+        .filter(|(parsed, _)| parsed.major == major && minor.is_none_or(|m| parsed.minor == m))
+        .max_by_key(|(parsed, _)| (parsed.pre == PreRelease::Final, *parsed))
+        .map(|(_, v)| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapper_version_rejects_other_go_files() {
+        assert_eq!(wrapper_version("go1.22.5"), Some("1.22.5"));
+        assert_eq!(wrapper_version("go1.23rc1"), Some("1.23rc1"));
+        assert_eq!(wrapper_version("go"), None);
+        assert_eq!(wrapper_version("golint"), None);
+        assert_eq!(wrapper_version("go1.22.5.bak"), None);
+    }
+
+    #[test]
+    fn version_compare_orders_releases() {
+        assert_eq!(version_compare("1.22.11", "1.22.5"), Ordering::Greater);
+        assert_eq!(version_compare("1.21.0", "1.22.0"), Ordering::Less);
+        assert_eq!(version_compare("1.20", "1.20.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn version_compare_orders_prereleases_before_final() {
+        assert_eq!(version_compare("1.23rc1", "1.23.0"), Ordering::Less);
+        assert_eq!(version_compare("1.23beta1", "1.23rc1"), Ordering::Less);
+        assert_eq!(version_compare("1.23rc1", "1.23rc2"), Ordering::Less);
+        assert_eq!(version_compare("1.23rc2", "1.22.11"), Ordering::Greater);
+        assert_eq!(version_compare("1.20rc1", "1.20"), Ordering::Less);
+    }
+
+    #[test]
+    fn version_order_is_total() {
+        assert_eq!(version_order("1.20", "1.20.0"), Ordering::Less);
+        assert_eq!(version_order("1.23beta1", "1.23beta2"), Ordering::Less);
+        assert_eq!(version_order("1.23rc1", "1.23beta2"), Ordering::Greater);
+        assert_eq!(version_order("bogus", "1.0"), Ordering::Less);
+        assert_eq!(version_order("bogus", "also-bogus"), Ordering::Greater);
+        assert_eq!(version_order("1.22.5", "1.22.5"), Ordering::Equal);
+    }
+
+    #[test]
+    fn version_parses_go_release_names() {
+        for name in ["1.22.11", "1.20", "1.23rc1", "1.21beta2"] {
+            assert_eq!(name.parse::<Version>().unwrap().to_string(), name);
+        }
+        assert_eq!("go1.22.11".parse::<Version>().unwrap().to_string(), "1.22.11");
+        assert!("1.22".parse::<Version>().unwrap().is_minor_only());
+
+        for name in ["1.22.x", "1.23.1rc1", "1", "1.22.", "1.22rc", "latest"] {
+            assert!(name.parse::<Version>().is_err(), "{} should not parse", name);
+        }
+    }
+
+    #[test]
+    fn partial_versions_prefer_stable_releases() {
+        let installed = ["go1.21.13", "go1.22.11", "go1.22.5", "go1.23rc1"];
+        assert_eq!(best_partial_match("1", installed), Some("go1.22.11"));
+        assert_eq!(best_partial_match("1.22", installed), Some("go1.22.11"));
+        assert_eq!(best_partial_match("1.23", installed), Some("go1.23rc1"));
+        assert_eq!(best_partial_match("1.24", installed), None);
+        assert_eq!(best_partial_match("1.22.x", installed), None);
+    }
+}